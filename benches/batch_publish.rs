@@ -0,0 +1,70 @@
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use iota_streams::app_channels::api::tangle::{Address, Author, Bytes, ChannelType};
+use std::time::Duration;
+use streams_examples::{
+    examples::generate_seed,
+    tools::publish_batch,
+    transport::{BatchTransport, MemoryTransport, StandInNode},
+};
+use tokio::runtime::Builder;
+
+// Round trip added to every request, roughly that of a nearby node
+const LATENCY: Duration = Duration::from_millis(5);
+
+fn payloads(count: usize) -> Vec<Bytes> {
+    (0..count)
+        .map(|i| Bytes(format!("Message {}", i).into_bytes()))
+        .collect()
+}
+
+// A fresh channel over a stand-in node with latency, returning the author and announcement link
+async fn channel() -> (Author<BatchTransport<StandInNode>>, BatchTransport<StandInNode>, Address) {
+    let node = StandInNode::new(MemoryTransport::new());
+    node.set_latency(LATENCY);
+    let transport = BatchTransport::new(node);
+    let mut author = Author::new(&generate_seed(), ChannelType::SingleBranch, transport.clone());
+    let announcement_link = author.send_announce().await.unwrap();
+    (author, transport, announcement_link)
+}
+
+async fn publish_sequential(count: usize) {
+    let (mut author, _transport, announcement_link) = channel().await;
+    let mut prev_msg_link = announcement_link;
+    for payload in payloads(count) {
+        let (msg_link, _seq) = author
+            .send_signed_packet(&prev_msg_link, &Bytes::default(), &payload)
+            .await
+            .unwrap();
+        prev_msg_link = msg_link;
+    }
+}
+
+async fn publish_batched(count: usize, concurrency: usize) {
+    let (mut author, transport, announcement_link) = channel().await;
+    publish_batch(&mut author, &transport, &announcement_link, &payloads(count), concurrency)
+        .await
+        .unwrap();
+}
+
+fn bench_publish_chain(c: &mut Criterion) {
+    let runtime = Builder::new_current_thread().enable_all().build().unwrap();
+    let mut group = c.benchmark_group("publish_chain");
+    group.sample_size(10);
+
+    for count in [10, 50] {
+        group.throughput(Throughput::Elements(count as u64));
+        group.bench_with_input(BenchmarkId::new("sequential", count), &count, |b, &count| {
+            b.iter(|| runtime.block_on(publish_sequential(count)))
+        });
+        for concurrency in [4, 16] {
+            let id = BenchmarkId::new(format!("batch_{}_in_flight", concurrency), count);
+            group.bench_with_input(id, &count, |b, &count| {
+                b.iter(|| runtime.block_on(publish_batched(count, concurrency)))
+            });
+        }
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_publish_chain);
+criterion_main!(benches);
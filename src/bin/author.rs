@@ -0,0 +1,167 @@
+use anyhow::{bail, Result};
+use iota_streams::app_channels::api::tangle::{Address, Author, Bytes, ChannelType, Transport};
+use core::str::FromStr;
+use std::{fs, path::Path};
+use streams_examples::{
+    cli::{load_or_create_seed, read_link, state_password, write_link, TransportSpec},
+    tools::{chunking::DEFAULT_CHUNK_SIZE, ChannelGraph, FileSender},
+    transport::{FileTransport, SqliteTransport},
+};
+
+const USAGE: &str = "Usage: author <fs:DIR|sqlite:FILE> <STATE_DIR> <COMMAND>
+
+Commands:
+    announce [OUT_FILE]         Create the channel and print the announcement link
+    subscribe <LINK|FILE|->     Process a subscription link provided by a subscriber
+    unsubscribe <LINK|FILE|->   Process an unsubscribe link, removing the subscriber from keyloads
+    keyload [OUT_FILE]          Grant all processed subscribers access to the branch
+    publish <MESSAGE>...        Publish signed packets to the branch
+    send-file <FILE> [OUT_FILE] Send a file in chunks and print the link of its manifest,
+                                resuming an interrupted transfer
+    graph <OUT_FILE>            Write the channel tree as Graphviz DOT (.dot) or Mermaid (.mmd)";
+
+/**
+ * The Author side of a channel running in its own process. State is exported to the state
+ * directory after every command, so each invocation picks up where the previous one left off.
+ * Links are exchanged with subscribers through files or stdin/stdout.
+ */
+#[tokio::main]
+async fn main() -> Result<()> {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if args.len() < 3 {
+        bail!(USAGE);
+    }
+
+    match TransportSpec::from_str(&args[0])? {
+        TransportSpec::Fs(dir) => run(FileTransport::new(dir)?, &args[1], &args[2..]).await,
+        TransportSpec::Sqlite(path) => run(SqliteTransport::new(path)?, &args[1], &args[2..]).await,
+    }
+}
+
+async fn run<T: Transport>(transport: T, state_dir: &str, command: &[String]) -> Result<()> {
+    let state_dir = Path::new(state_dir);
+    let state_path = state_dir.join("state.bin");
+    let last_link_path = state_dir.join("last_link");
+    let ann_link_path = state_dir.join("ann_link");
+    let transfer_path = state_dir.join("transfer.json");
+    let password = state_password();
+
+    // An interrupted transfer holds the Author state as of the last chunk it sent
+    let resumed = if command[0] == "send-file" && transfer_path.exists() {
+        FileSender::open(&transfer_path)?.author(&password, transport.clone()).await?
+    } else {
+        None
+    };
+    let mut author = match resumed {
+        Some(author) => author,
+        None if state_path.exists() => {
+            Author::import(&fs::read(&state_path)?, &password, transport.clone()).await?
+        }
+        None => {
+            let seed = load_or_create_seed(state_dir)?;
+            Author::new(&seed, ChannelType::SingleBranch, transport.clone())
+        }
+    };
+
+    match command[0].as_str() {
+        "announce" => {
+            if last_link_path.exists() {
+                bail!("Channel has already been announced from {}", state_dir.display());
+            }
+            // Create the channel with an announcement message, this link is handed to subscribers
+            let announcement_link = author.send_announce().await?;
+            fs::write(&ann_link_path, announcement_link.to_string())?;
+            fs::write(&last_link_path, announcement_link.to_string())?;
+            write_link(&announcement_link, command.get(1).map(String::as_str))?;
+        }
+        "subscribe" => {
+            let arg = command.get(1).map(String::as_str).unwrap_or("-");
+            let sub_link = read_link(arg)?;
+            author.receive_subscribe(&sub_link).await?;
+            eprintln!("Processed subscription {}", sub_link);
+        }
+        "unsubscribe" => {
+            let arg = command.get(1).map(String::as_str).unwrap_or("-");
+            let unsub_link = read_link(arg)?;
+            author.receive_unsubscribe(&unsub_link).await?;
+            eprintln!("Processed unsubscribe {}", unsub_link);
+        }
+        "keyload" => {
+            // Keyloads are attached to the tip of the branch so that they are found by subscribers
+            // reading forward from wherever they are
+            let prev_link = read_last_link(&last_link_path)?;
+            let (keyload_link, _seq) = author.send_keyload_for_everyone(&prev_link).await?;
+            fs::write(&last_link_path, keyload_link.to_string())?;
+            write_link(&keyload_link, command.get(1).map(String::as_str))?;
+        }
+        "publish" => {
+            let mut prev_link = read_last_link(&last_link_path)?;
+            for input in &command[1..] {
+                let (msg_link, _seq) = author.send_signed_packet(
+                    &prev_link,
+                    &Bytes::default(),
+                    &Bytes(input.as_bytes().to_vec()),
+                ).await?;
+                eprintln!("Sent msg: {}, tangle index: {:#}", msg_link, msg_link.to_msg_index());
+                // Saved after every packet, so that a send failing part way through leaves the
+                // state matching the packets already sent
+                fs::write(&state_path, author.export(&password).await?)?;
+                fs::write(&last_link_path, msg_link.to_string())?;
+                prev_link = msg_link;
+            }
+        }
+        "send-file" => {
+            let file = match command.get(1) {
+                Some(file) => Path::new(file),
+                None => bail!(USAGE),
+            };
+            let payload = fs::read(file)?;
+            let mut sender = if transfer_path.exists() {
+                FileSender::open(&transfer_path)?
+            } else {
+                let name = file
+                    .file_name()
+                    .map_or_else(String::new, |name| name.to_string_lossy().to_string());
+                let prev_link = read_last_link(&last_link_path)?;
+                FileSender::new(&transfer_path, &name, &payload, &prev_link, DEFAULT_CHUNK_SIZE)?
+            };
+            let manifest_link = sender.send(&mut author, &payload, &password, |progress| {
+                eprintln!(
+                    "Sent chunk {}/{} ({}/{} bytes)",
+                    progress.chunks_done, progress.chunk_count, progress.bytes_done, progress.size
+                );
+            }).await?;
+            fs::write(&last_link_path, manifest_link.to_string())?;
+            fs::remove_file(&transfer_path)?;
+            write_link(&manifest_link, command.get(2).map(String::as_str))?;
+        }
+        "graph" => {
+            let output = match command.get(1) {
+                Some(output) => Path::new(output),
+                None => bail!(USAGE),
+            };
+            let ann_link = read_last_link(&ann_link_path)?;
+            let tip = read_last_link(&last_link_path)?;
+            // The Author's own messages are never fetched, so the branch is walked back from its
+            // tip, through a copy so that receiving them leaves the saved state untouched
+            let mut author_copy =
+                Author::import(&author.export(&password).await?, &password, transport).await?;
+            let mut graph = ChannelGraph::new(&ann_link);
+            graph.walk_back("Author", &mut author_copy, &[tip]).await?;
+            graph.write(output)?;
+            eprintln!("Wrote {} messages to {}", graph.nodes().len(), output.display());
+        }
+        _ => bail!(USAGE),
+    }
+
+    fs::create_dir_all(state_dir)?;
+    fs::write(&state_path, author.export(&password).await?)?;
+    Ok(())
+}
+
+fn read_last_link(path: &Path) -> Result<Address> {
+    if !path.exists() {
+        bail!("Channel has not been announced yet, run the announce command first");
+    }
+    Ok(Address::from_str(fs::read_to_string(path)?.trim())?)
+}
@@ -0,0 +1,157 @@
+use anyhow::{bail, Result};
+use iota_streams::app_channels::api::tangle::{Address, MessageContent, Subscriber, Transport};
+use core::str::FromStr;
+use std::{fs, path::Path};
+use streams_examples::{
+    cli::{load_or_create_seed, read_link, state_password, write_link, TransportSpec},
+    tools::{compression::decompress_message, ChannelGraph, FileReceiver},
+    transport::{FileTransport, SqliteTransport},
+};
+
+const USAGE: &str = "Usage: subscriber <fs:DIR|sqlite:FILE> <STATE_DIR> <COMMAND>
+
+Commands:
+    join <LINK|FILE|-> [OUT_FILE]   Receive the announcement and print a subscription link
+    read                            Print all new messages readable by this subscriber
+    leave [OUT_FILE]                Print an unsubscribe link for the Author to process
+    receive-file <OUT_FILE>         Receive a file sent with send-file, resuming an interrupted
+                                    transfer
+    graph <OUT_FILE>                Write the channel tree readable by this subscriber as Graphviz
+                                    DOT (.dot) or Mermaid (.mmd)";
+
+/**
+ * A Subscriber running in its own process, mirroring how a device onboards onto a channel: it is
+ * handed the announcement link, replies with a subscription link for the Author to process, and
+ * can then read any branch it has been given access to.
+ */
+#[tokio::main]
+async fn main() -> Result<()> {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if args.len() < 3 {
+        bail!(USAGE);
+    }
+
+    match TransportSpec::from_str(&args[0])? {
+        TransportSpec::Fs(dir) => run(FileTransport::new(dir)?, &args[1], &args[2..]).await,
+        TransportSpec::Sqlite(path) => run(SqliteTransport::new(path)?, &args[1], &args[2..]).await,
+    }
+}
+
+async fn run<T: Transport>(transport: T, state_dir: &str, command: &[String]) -> Result<()> {
+    let state_dir = Path::new(state_dir);
+    let state_path = state_dir.join("state.bin");
+    let sub_link_path = state_dir.join("sub_link");
+    let ann_link_path = state_dir.join("ann_link");
+    let transfer_path = state_dir.join("transfer.json");
+    let password = state_password();
+
+    // An interrupted transfer holds the Subscriber state as of the last round it read
+    let resumed = match (command[0].as_str(), command.get(1)) {
+        ("receive-file", Some(output)) if transfer_path.exists() => {
+            FileReceiver::open(&transfer_path, output)?
+                .subscriber(&password, transport.clone())
+                .await?
+        }
+        _ => None,
+    };
+    let mut subscriber = match resumed {
+        Some(subscriber) => subscriber,
+        None if state_path.exists() => {
+            Subscriber::import(&fs::read(&state_path)?, &password, transport.clone()).await?
+        }
+        None => {
+            let seed = load_or_create_seed(state_dir)?;
+            Subscriber::new(&seed, transport.clone())
+        }
+    };
+
+    match command[0].as_str() {
+        "join" => {
+            let arg = command.get(1).map(String::as_str).unwrap_or("-");
+            let ann_link = read_link(arg)?;
+            subscriber.receive_announcement(&ann_link).await?;
+            fs::create_dir_all(state_dir)?;
+            fs::write(&ann_link_path, ann_link.to_string())?;
+
+            // The subscription link needs to be provided to the Author to complete subscription
+            let sub_link = subscriber.send_subscribe(&ann_link).await?;
+            fs::write(&sub_link_path, sub_link.to_string())?;
+            write_link(&sub_link, command.get(2).map(String::as_str))?;
+        }
+        "leave" => {
+            if !sub_link_path.exists() {
+                bail!("Subscriber has not joined a channel yet, run the join command first");
+            }
+            // The unsubscribe message is linked to the original subscription message
+            let sub_link = Address::from_str(fs::read_to_string(&sub_link_path)?.trim())?;
+            let unsub_link = subscriber.send_unsubscribe(&sub_link).await?;
+            fs::remove_file(&sub_link_path)?;
+            write_link(&unsub_link, command.get(1).map(String::as_str))?;
+        }
+        "read" => {
+            for mut msg in subscriber.fetch_all_next_msgs().await {
+                // Compressed payloads are decoded, and those with a codec unknown to this build
+                // are skipped rather than printed as garbage
+                if let Err(e) = decompress_message(&mut msg) {
+                    eprintln!("Skipping {}: {}", msg.link, e);
+                    continue;
+                }
+                match msg.body {
+                    MessageContent::SignedPacket {
+                        pk: _,
+                        public_payload: _,
+                        masked_payload,
+                    } => println!("{}", String::from_utf8_lossy(&masked_payload.0)),
+                    MessageContent::TaggedPacket {
+                        public_payload: _,
+                        masked_payload,
+                    } => println!("{}", String::from_utf8_lossy(&masked_payload.0)),
+                    MessageContent::Keyload => eprintln!("Keyload: {}", msg.link),
+                    _ => (),
+                }
+            }
+        }
+        "receive-file" => {
+            let output = match command.get(1) {
+                Some(output) => output,
+                None => bail!(USAGE),
+            };
+            let mut receiver = FileReceiver::open(&transfer_path, output)?;
+            let complete = receiver.receive(&mut subscriber, &password, |progress| {
+                eprintln!(
+                    "Received chunk {}/{} ({}/{} bytes)",
+                    progress.chunks_done, progress.chunk_count, progress.bytes_done, progress.size
+                );
+            }).await?;
+            if complete {
+                fs::remove_file(&transfer_path)?;
+                eprintln!("Received and verified {}", output);
+            } else {
+                eprintln!("Transfer incomplete, run receive-file again to resume");
+            }
+        }
+        "graph" => {
+            let output = match command.get(1) {
+                Some(output) => Path::new(output),
+                None => bail!(USAGE),
+            };
+            if !ann_link_path.exists() {
+                bail!("Subscriber has not joined a channel yet, run the join command first");
+            }
+            // Walked by a fresh reader with the same keys, so the whole channel is drawn without
+            // moving this subscriber past messages it hasn't read yet
+            let ann_link = Address::from_str(fs::read_to_string(&ann_link_path)?.trim())?;
+            let mut reader = Subscriber::new(&load_or_create_seed(state_dir)?, transport);
+            reader.receive_announcement(&ann_link).await?;
+            let mut graph = ChannelGraph::new(&ann_link);
+            graph.walk("Subscriber", &mut reader).await;
+            graph.write(output)?;
+            eprintln!("Wrote {} messages to {}", graph.nodes().len(), output.display());
+        }
+        _ => bail!(USAGE),
+    }
+
+    fs::create_dir_all(state_dir)?;
+    fs::write(&state_path, subscriber.export(&password).await?)?;
+    Ok(())
+}
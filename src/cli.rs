@@ -0,0 +1,71 @@
+use anyhow::{bail, Result};
+use iota_streams::app_channels::api::tangle::Address;
+use core::str::FromStr;
+use std::{
+    fs,
+    io::{self, BufRead},
+    path::{Path, PathBuf},
+};
+
+use crate::examples::generate_seed;
+
+pub const DEFAULT_PASSWORD: &str = "Password";
+
+// The shared local transport the author and subscriber binaries talk over
+pub enum TransportSpec {
+    Fs(PathBuf),
+    Sqlite(PathBuf),
+}
+
+impl FromStr for TransportSpec {
+    type Err = anyhow::Error;
+
+    // Accepts `fs:<directory>` or `sqlite:<database file>`
+    fn from_str(spec: &str) -> Result<Self> {
+        match spec.split_once(':') {
+            Some(("fs", dir)) => Ok(TransportSpec::Fs(PathBuf::from(dir))),
+            Some(("sqlite", path)) => Ok(TransportSpec::Sqlite(PathBuf::from(path))),
+            _ => bail!("Unknown transport '{}', expected fs:<dir> or sqlite:<file>", spec),
+        }
+    }
+}
+
+// Read the seed stored in the state directory, generating and storing a new one on first use
+pub fn load_or_create_seed(state_dir: &Path) -> Result<String> {
+    let path = state_dir.join("seed");
+    if path.exists() {
+        return Ok(fs::read_to_string(path)?.trim().to_string());
+    }
+    fs::create_dir_all(state_dir)?;
+    let seed = generate_seed();
+    fs::write(path, &seed)?;
+    Ok(seed)
+}
+
+pub fn state_password() -> String {
+    std::env::var("STREAMS_STATE_PASSWORD").unwrap_or_else(|_| DEFAULT_PASSWORD.to_string())
+}
+
+// Links can be handed over directly, as a path to a file containing the link, or as `-` to read
+// the link from stdin
+pub fn read_link(arg: &str) -> Result<Address> {
+    let link = if arg == "-" {
+        let mut line = String::new();
+        io::stdin().lock().read_line(&mut line)?;
+        line
+    } else if Path::new(arg).is_file() {
+        fs::read_to_string(arg)?
+    } else {
+        arg.to_string()
+    };
+    Ok(Address::from_str(link.trim())?)
+}
+
+// Print a link to stdout so it can be piped to the other party, optionally writing it to a file
+pub fn write_link(link: &Address, out: Option<&str>) -> Result<()> {
+    println!("{}", link);
+    if let Some(path) = out {
+        fs::write(path, link.to_string())?;
+    }
+    Ok(())
+}
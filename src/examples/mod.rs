@@ -6,11 +6,24 @@ pub use multi_publisher::*;
 pub use single_publisher::*;
 pub use utility::*;
 
-use anyhow::Result;
+use anyhow::{bail, Result};
 use iota_streams::app_channels::api::tangle::{MessageContent, UnwrappedMessage};
+use rand::Rng;
 
 pub const ALPH9: &str = "ABCDEFGHIJKLMNOPQRSTUVWXYZ9";
 
+// Generate a random 81 character seed
+pub fn generate_seed() -> String {
+    (0..81)
+        .map(|_| {
+            ALPH9
+                .chars()
+                .nth(rand::thread_rng().gen_range(0, 27))
+                .unwrap()
+        })
+        .collect::<String>()
+}
+
 // Iterate through the retrieved messages to ensure they match those that were sent
 pub fn verify_messages(sent_msgs: &[&str], retrieved_msgs: Vec<UnwrappedMessage>) -> Result<()> {
     let processed_msgs = retrieved_msgs
@@ -23,6 +36,10 @@ pub fn verify_messages(sent_msgs: &[&str], retrieved_msgs: Vec<UnwrappedMessage>
                     public_payload: _,
                     masked_payload,
                 } => String::from_utf8(masked_payload.0.to_vec()).unwrap(),
+                MessageContent::TaggedPacket {
+                    public_payload: _,
+                    masked_payload,
+                } => String::from_utf8(masked_payload.0.to_vec()).unwrap(),
                 _ => String::default(),
             }
         })
@@ -42,3 +59,28 @@ pub fn verify_messages(sent_msgs: &[&str], retrieved_msgs: Vec<UnwrappedMessage>
 
     Ok(())
 }
+
+// Assert that an operation failed, reporting the error it returned
+pub fn expect_failure<R>(case: &str, result: Result<R>) -> Result<String> {
+    match result {
+        Ok(_) => bail!("{} was expected to fail but succeeded", case),
+        Err(e) => {
+            println!("{} failed as expected:\n\t{}", case, e);
+            Ok(e.to_string())
+        }
+    }
+}
+
+// Assert that an operation failed with an error mentioning one of `fragments`, ignoring case, so
+// that failing for some other reason doesn't pass as the expected failure
+pub fn expect_error<R>(case: &str, result: Result<R>, fragments: &[&str]) -> Result<String> {
+    let error = expect_failure(case, result)?;
+    let lowercase = error.to_lowercase();
+    if !fragments.iter().any(|fragment| lowercase.contains(&fragment.to_lowercase())) {
+        bail!(
+            "{} failed with an unexpected error, expected one mentioning {:?}: {}",
+            case, fragments, error
+        );
+    }
+    Ok(error)
+}
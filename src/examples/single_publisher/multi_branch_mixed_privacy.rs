@@ -1,35 +1,24 @@
 use iota_streams::{
-    app::transport::tangle::client::Client,
     app_channels::api::{
         psk_from_seed,
         pskid_from_psk,
         tangle::{
-            Address, Author, Bytes, ChannelType, PublicKey, Subscriber, UnwrappedMessage,
+            Address, Author, Bytes, ChannelType, PublicKey, Subscriber, UnwrappedMessage, Transport,
         }
     },
     core::{println, Result},
 };
 
-use crate::examples::{verify_messages, ALPH9};
+use crate::examples::{generate_seed, verify_messages};
 use rand::Rng;
 use core::str::FromStr;
 
-pub async fn example(node_url: &str) -> Result<()> {
+pub async fn example<T: Transport>(transport: T) -> Result<()> {
     // Generate a unique seed for the author
-    let seed: &str = &(0..81)
-        .map(|_| {
-            ALPH9
-                .chars()
-                .nth(rand::thread_rng().gen_range(0, 27))
-                .unwrap()
-        })
-        .collect::<String>();
-
-    // Create the Transport Client
-    let client = Client::new_from_url(node_url);
+    let seed: &str = &generate_seed();
 
     // Generate an Author
-    let mut author = Author::new(seed, ChannelType::MultiBranch, client.clone());
+    let mut author = Author::new(seed, ChannelType::MultiBranch, transport.clone());
 
     // Create the channel with an announcement message. Make sure to save the resulting link somewhere,
     let announcement_link = author.send_announce().await?;
@@ -53,11 +42,11 @@ pub async fn example(node_url: &str) -> Result<()> {
     // In their own separate instances generate the subscriber(s) that will be attaching to the channel
 
     // This subscriber will subscribe traditionally
-    let mut subscriber_a = Subscriber::new("SubscriberA", client.clone());
+    let mut subscriber_a = Subscriber::new("SubscriberA", transport.clone());
     // This subscriber will use a PSK
-    let mut subscriber_b = Subscriber::new("SubscriberB", client.clone());
+    let mut subscriber_b = Subscriber::new("SubscriberB", transport.clone());
     // This subscriber will not subscribe at all
-    let mut subscriber_c = Subscriber::new("SubscriberC", client);
+    let mut subscriber_c = Subscriber::new("SubscriberC", transport);
 
     // Generate an Address object from the provided announcement link string from the Author
     let ann_address = Address::from_str(&ann_link_string)?;
@@ -0,0 +1,124 @@
+use anyhow::ensure;
+use iota_streams::{
+    app_channels::api::tangle::{
+        Author, Bytes, ChannelType, MessageContent, PublicKey, Subscriber, Transport,
+    },
+    core::{println, Result},
+};
+
+use crate::examples::{generate_seed, verify_messages};
+use crate::tools::{backfill, rotation::Membership, Provenance};
+
+/**
+ * This example grants a late joiner access to the history of a private branch. Keyloads can't
+ * grant access retroactively, so once Subscriber B has joined the original branch (published by
+ * both the Author and Subscriber A) is walked by a fresh instance of Subscriber A, and the Author
+ * re-publishes its messages into a new branch keyloaded for Subscribers A and B. Each re-published
+ * message keeps its masked payload and carries its provenance, the original link and publisher, in
+ * its public payload.
+ */
+pub async fn example<T: Transport>(transport: T) -> Result<()> {
+    // Generate a unique seed for the author
+    let seed: &str = &generate_seed();
+
+    // Generate an Author
+    let mut author = Author::new(seed, ChannelType::MultiBranch, transport.clone());
+    let announcement_link = author.send_announce().await?;
+    println!(
+        "Announcement Link: {}\nTangle Index: {:#}\n",
+        announcement_link, announcement_link.to_msg_index()
+    );
+
+    let mut subscriber_a = Subscriber::new("SubscriberA", transport.clone());
+    subscriber_a.receive_announcement(&announcement_link).await?;
+    let subscribe_msg_a = subscriber_a.send_subscribe(&announcement_link).await?;
+    let sub_a_pk = *subscriber_a.get_public_key().as_bytes();
+    author.receive_subscribe(&subscribe_msg_a).await?;
+
+    // The original branch is only readable by Subscriber A
+    let (keyload_link, _seq) = author.send_keyload(
+        &announcement_link,
+        &vec![PublicKey::from_bytes(&sub_a_pk)?.into()],
+    ).await?;
+    println!("Sent Keyload for Sub A: {}", keyload_link);
+
+    // Both the Author and Subscriber A publish in the original branch
+    let history = vec!["History", "Published", "Before", "B", "Joined"];
+    let mut prev_msg_link = keyload_link.clone();
+    let mut seq_msg_link = None;
+    for (i, input) in history.iter().enumerate() {
+        let payload = Bytes(input.as_bytes().to_vec());
+        let (msg_link, seq_link) = if i < 3 {
+            author.send_signed_packet(&prev_msg_link, &Bytes::default(), &payload).await?
+        } else {
+            subscriber_a.sync_state().await;
+            subscriber_a.send_signed_packet(&prev_msg_link, &Bytes::default(), &payload).await?
+        };
+        println!("Sent msg: {}, tangle index: {:#}", msg_link, msg_link.to_msg_index());
+        prev_msg_link = msg_link;
+        seq_msg_link = seq_link;
+    }
+    let seq_msg_link = seq_msg_link.unwrap();
+
+    // The Author catches up with the messages published by Subscriber A
+    author.fetch_all_next_msgs().await;
+    subscriber_a.fetch_all_next_msgs().await;
+
+    // ------------------------------------------------------------------
+    // Subscriber B joins after the original branch was published
+    let mut subscriber_b = Subscriber::new("SubscriberB", transport.clone());
+    subscriber_b.receive_announcement(&announcement_link).await?;
+    let subscribe_msg_b = subscriber_b.send_subscribe(&announcement_link).await?;
+    let sub_b_pk = *subscriber_b.get_public_key().as_bytes();
+    author.receive_subscribe(&subscribe_msg_b).await?;
+
+    // The original branch is walked by a freshly started instance of Subscriber A, which has
+    // access to it and hasn't read any of it yet. The history is re-published in a new branch for
+    // the new member set, attached to the last sequence message so that both subscribers find it
+    let mut history_reader = Subscriber::new("SubscriberA", transport.clone());
+    history_reader.receive_announcement(&announcement_link).await?;
+    let backfilled = backfill(
+        &mut author,
+        &mut history_reader,
+        &keyload_link,
+        &seq_msg_link,
+        &Membership::Subscribers(vec![sub_a_pk, sub_b_pk]),
+    ).await?;
+    println!("Sent backfill Keyload: {}", backfilled.keyload_link);
+    for (original_link, msg_link) in &backfilled.republished {
+        println!("Re-published {} at {}", original_link, msg_link);
+    }
+
+    // -----------------------------------------------------------------------------
+    println!("\nVerifying message retrieval: SubscriberB");
+    let retrieved = subscriber_b.fetch_all_next_msgs().await;
+    for msg in &retrieved {
+        if let MessageContent::SignedPacket { public_payload, .. } = &msg.body {
+            let provenance = Provenance::from_public_payload(public_payload)?;
+            let original = backfilled
+                .republished
+                .iter()
+                .find(|(_original, republished)| republished == &msg.link)
+                .map(|(original, _republished)| original.to_string());
+            ensure!(
+                original.as_ref() == Some(&provenance.original_link),
+                "Provenance of {} does not match its original link", msg.link
+            );
+            ensure!(
+                provenance.publisher.is_some(),
+                "Provenance of {} is missing the original publisher", msg.link
+            );
+            println!(
+                "{} was originally published at {} by {}",
+                msg.link, provenance.original_link, provenance.publisher.unwrap_or_default()
+            );
+        }
+    }
+    verify_messages(&history, retrieved)?;
+
+    // Subscriber A reads the backfilled copies as well, and can tell them apart by their provenance
+    println!("\nVerifying message retrieval: SubscriberA");
+    verify_messages(&history, subscriber_a.fetch_all_next_msgs().await)?;
+
+    Ok(())
+}
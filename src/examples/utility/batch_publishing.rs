@@ -0,0 +1,137 @@
+use anyhow::{anyhow, ensure};
+use iota_streams::{
+    app_channels::api::tangle::{Author, Bytes, ChannelType, Subscriber},
+    core::{println, Result},
+};
+
+use crate::examples::{generate_seed, verify_messages};
+use crate::tools::{publish_batch, BatchError};
+use crate::transport::{BatchTransport, MemoryTransport, StandInNode};
+use std::time::{Duration, Instant};
+
+// Round trip added to every request to the stand-in node
+const LATENCY: Duration = Duration::from_millis(20);
+const CONCURRENCY: usize = 5;
+
+/**
+ * This example compares publishing a chain of messages one awaited round trip at a time with
+ * publishing it as a batch. The node is a local stand-in with a fixed latency on every request.
+ * The sequential loop waits for each message to be sent before preparing the next one, while the
+ * batch prepares the whole linked chain up front and submits it with several sends in flight at
+ * once. A further batch is published while the node fails some of its sends, and the unsent
+ * packets it reports are submitted again. Every chain is read back by a subscriber in order.
+ *
+ * See `benches/batch_publish.rs` for a benchmark over longer chains.
+ */
+pub async fn example() -> Result<()> {
+    // Generate a unique seed for the author
+    let seed: &str = &generate_seed();
+
+    let node = StandInNode::new(MemoryTransport::new());
+    node.set_latency(LATENCY);
+    let transport = BatchTransport::new(node.clone());
+
+    // Generate an Author
+    let mut author = Author::new(seed, ChannelType::SingleBranch, transport.clone());
+    let announcement_link = author.send_announce().await?;
+    println!(
+        "Announcement Link: {}\nTangle Index: {:#}\n",
+        announcement_link, announcement_link.to_msg_index()
+    );
+
+    let msg_inputs = vec![
+        "These",
+        "Messages",
+        "Will",
+        "Be",
+        "Sent",
+        "One",
+        "At",
+        "A",
+        "Time",
+        "Sequentially",
+    ];
+
+    // Each message is sent before the next is prepared
+    let start = Instant::now();
+    let mut prev_msg_link = announcement_link.clone();
+    for input in &msg_inputs {
+        let (msg_link, _seq_link) = author.send_signed_packet(
+            &prev_msg_link,
+            &Bytes::default(),
+            &Bytes(input.as_bytes().to_vec()),
+        ).await?;
+        prev_msg_link = msg_link;
+    }
+    let sequential = start.elapsed();
+    println!("Published {} msgs sequentially in {:?}", msg_inputs.len(), sequential);
+
+    // The whole chain is prepared first and then submitted concurrently
+    let batch_inputs = vec![
+        "These",
+        "Messages",
+        "Will",
+        "Be",
+        "Submitted",
+        "Together",
+        "In",
+        "A",
+        "Single",
+        "Batch",
+    ];
+    let payloads = batch_inputs
+        .iter()
+        .map(|input| Bytes(input.as_bytes().to_vec()))
+        .collect::<Vec<Bytes>>();
+    let start = Instant::now();
+    let links = publish_batch(&mut author, &transport, &prev_msg_link, &payloads, CONCURRENCY).await?;
+    let batched = start.elapsed();
+    println!(
+        "Published {} msgs as a batch ({} in flight) in {:?}",
+        links.len(), CONCURRENCY, batched
+    );
+    for (msg_link, _seq_link) in &links {
+        println!("Sent msg: {}, tangle index: {:#}", msg_link, msg_link.to_msg_index());
+    }
+    ensure!(batched < sequential, "Batch publishing was not faster than the sequential loop");
+
+    // The node fails some of the sends of the next batch. The Author has already moved past every
+    // packet of it, so the unsent ones are reported and submitted again rather than re-published
+    let retried_inputs = vec!["Partly", "Failed", "Batch"];
+    let payloads = retried_inputs
+        .iter()
+        .map(|input| Bytes(input.as_bytes().to_vec()))
+        .collect::<Vec<Bytes>>();
+    let last_msg_link = links
+        .last()
+        .map_or(prev_msg_link, |(msg_link, _seq_link)| msg_link.clone());
+    node.fail_next(2);
+    let error = publish_batch(&mut author, &transport, &last_msg_link, &payloads, CONCURRENCY)
+        .await
+        .err()
+        .ok_or_else(|| anyhow!("Expected the batch to fail while the node fails sends"))?
+        .downcast::<BatchError>()?;
+    println!("\n{}", error);
+    for (msg_link, _seq_link) in &error.unsent {
+        println!("Unsent msg: {}", msg_link);
+    }
+    let resent = transport.submit(CONCURRENCY).await?;
+    println!("Submitted the {} queued messages again", resent);
+
+    // -----------------------------------------------------------------------------
+    let mut subscriber_a = Subscriber::new("SubscriberA", node);
+    subscriber_a.receive_announcement(&announcement_link).await?;
+    let mut retrieved = subscriber_a.fetch_all_next_msgs().await;
+    ensure!(
+        retrieved.len() == msg_inputs.len() + batch_inputs.len() + retried_inputs.len(),
+        "Not every message was read"
+    );
+    let retried_msgs = retrieved.split_off(msg_inputs.len() + batch_inputs.len());
+    let batch_msgs = retrieved.split_off(msg_inputs.len());
+    println!("\nVerifying message retrieval: SubscriberA");
+    verify_messages(&msg_inputs, retrieved)?;
+    verify_messages(&batch_inputs, batch_msgs)?;
+    verify_messages(&retried_inputs, retried_msgs)?;
+
+    Ok(())
+}
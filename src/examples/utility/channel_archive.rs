@@ -0,0 +1,79 @@
+use iota_streams::{
+    app_channels::api::tangle::{Address, Author, Bytes, ChannelType, Subscriber, Transport},
+    core::{println, Result},
+};
+
+use crate::examples::{generate_seed, verify_messages};
+use crate::tools::ChannelArchive;
+use core::str::FromStr;
+
+/**
+ * This example exports the contents of a private channel to a JSON archive as Subscriber A, then
+ * loads the raw messages of the archive into an offline transport where a fresh instance of
+ * Subscriber A re-reads the channel without access to the original transport.
+ */
+pub async fn example<T: Transport>(transport: T) -> Result<()> {
+    // Generate a unique seed for the author
+    let seed: &str = &generate_seed();
+
+    // Generate an Author
+    let mut author = Author::new(seed, ChannelType::SingleBranch, transport.clone());
+
+    // Create the channel with an announcement message. Make sure to save the resulting link somewhere,
+    let announcement_link = author.send_announce().await?;
+    // This link acts as a root for the channel itself
+    let ann_link_string = announcement_link.to_string();
+    println!(
+        "Announcement Link: {}\nTangle Index: {:#}\n",
+        ann_link_string, announcement_link.to_msg_index()
+    );
+
+    // ------------------------------------------------------------------
+    // In their own separate instances generate the subscriber(s) that will be attaching to the channel
+    let mut subscriber_a = Subscriber::new("SubscriberA", transport.clone());
+
+    let ann_address = Address::from_str(&ann_link_string)?;
+    subscriber_a.receive_announcement(&ann_address).await?;
+    let subscribe_msg_a = subscriber_a.send_subscribe(&ann_address).await?;
+    // ----------------------------------------------------------------------
+
+    author.receive_subscribe(&subscribe_msg_a).await?;
+    let (keyload_link, _seq) = author.send_keyload_for_everyone(&announcement_link).await?;
+
+    // Author will now send signed encrypted messages in a chain
+    let msg_inputs = vec!["These", "Messages", "Will", "Be", "Archived"];
+
+    let mut prev_msg_link = keyload_link;
+    for input in &msg_inputs {
+        let (msg_link, _seq_link) = author.send_signed_packet(
+            &prev_msg_link,
+            &Bytes(format!("public {}", input).into_bytes()),
+            &Bytes(input.as_bytes().to_vec()),
+        ).await?;
+        println!("Sent msg: {}, tangle index: {:#}", msg_link, msg_link.to_msg_index());
+        prev_msg_link = msg_link;
+    }
+
+    // -----------------------------------------------------------------------------
+    // Subscriber A exports everything it can read to a JSON archive
+    let mut source = transport;
+    let archive =
+        ChannelArchive::export("SubscriberA", &announcement_link, &mut subscriber_a, &mut source).await?;
+    std::fs::write("./channel_archive.json", archive.to_json()?)?;
+    println!(
+        "\nArchived {} messages ({} raw) to ./channel_archive.json",
+        archive.messages.len(), archive.raw_messages.len()
+    );
+
+    // The archive can be loaded back into an offline transport and read again by the same identity
+    let archive = ChannelArchive::from_json(&std::fs::read_to_string("./channel_archive.json")?)?;
+    let offline = archive.into_offline_transport().await?;
+
+    let mut offline_subscriber = Subscriber::new("SubscriberA", offline);
+    offline_subscriber.receive_announcement(&ann_address).await?;
+    let retrieved = offline_subscriber.fetch_all_next_msgs().await;
+    println!("\nVerifying message retrieval from the offline archive");
+    verify_messages(&msg_inputs, retrieved)?;
+
+    Ok(())
+}
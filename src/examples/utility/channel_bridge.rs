@@ -0,0 +1,133 @@
+use anyhow::ensure;
+use iota_streams::{
+    app_channels::api::tangle::{
+        Address, Author, Bytes, ChannelType, MessageContent, Subscriber, Transport,
+        UnwrappedMessage,
+    },
+    core::{println, Result},
+};
+
+use crate::cli::DEFAULT_PASSWORD;
+use crate::examples::{generate_seed, verify_messages};
+use crate::tools::Bridge;
+
+const CHECKPOINT_PATH: &str = "./bridge_checkpoint.json";
+const PUBLIC_PREFIX: &str = "public:";
+
+/**
+ * This example mirrors selected messages from a private channel into a public one. The bridge
+ * subscribes to the private channel like any other subscriber, and republishes the messages marked
+ * as public (with the marker stripped) as the Author of the public channel.
+ *
+ * The bridge persists a checkpoint to `./bridge_checkpoint.json` as it goes. Halfway through, the
+ * bridge is dropped and resumed from the checkpoint, and is then run again with nothing new to
+ * mirror, showing that readers of the public channel see every selected message exactly once.
+ */
+pub async fn example<T: Transport>(transport: T) -> Result<()> {
+    // Generate a unique seed for the author
+    let seed: &str = &generate_seed();
+
+    // The private source channel
+    let mut source_author = Author::new(seed, ChannelType::SingleBranch, transport.clone());
+    let source_announcement = source_author.send_announce().await?;
+    println!("Source Announcement Link: {}", source_announcement);
+
+    // The bridge subscribes to the source channel
+    let mut bridge_reader = Subscriber::new("BridgeReader", transport.clone());
+    bridge_reader.receive_announcement(&source_announcement).await?;
+    let subscribe_link = bridge_reader.send_subscribe(&source_announcement).await?;
+    source_author.receive_subscribe(&subscribe_link).await?;
+    let (keyload_link, _seq) = source_author.send_keyload_for_everyone(&source_announcement).await?;
+
+    // The public destination channel is owned by the bridge
+    let seed: &str = &generate_seed();
+    let mut bridge_author = Author::new(seed, ChannelType::SingleBranch, transport.clone());
+    let destination_announcement = bridge_author.send_announce().await?;
+    println!("Destination Announcement Link: {}\n", destination_announcement);
+
+    let mut bridge = Bridge::new(
+        bridge_reader,
+        bridge_author,
+        &destination_announcement,
+        CHECKPOINT_PATH,
+        DEFAULT_PASSWORD,
+    ).await?;
+
+    // ------------------------------------------------------------------
+    let first_inputs = vec!["public:Hello", "internal reading 1", "public:From", "internal reading 2"];
+    let mut prev_msg_link = keyload_link;
+    for input in &first_inputs {
+        let (msg_link, _seq) = source_author.send_signed_packet(
+            &prev_msg_link,
+            &Bytes::default(),
+            &Bytes(input.as_bytes().to_vec()),
+        ).await?;
+        println!("Sent source msg: {}", msg_link);
+        prev_msg_link = msg_link;
+    }
+
+    let mirrored = bridge.run(mirror_public).await?;
+    print_mirrored(&mirrored);
+    ensure!(mirrored.len() == 2, "Expected 2 messages to be mirrored, mirrored {}", mirrored.len());
+
+    // The bridge goes down, and the source channel carries on
+    drop(bridge);
+    println!("\nBridge stopped, checkpoint written to {}\n", CHECKPOINT_PATH);
+
+    let second_inputs = vec!["internal reading 3", "public:The", "public:Bridge"];
+    for input in &second_inputs {
+        let (msg_link, _seq) = source_author.send_signed_packet(
+            &prev_msg_link,
+            &Bytes::default(),
+            &Bytes(input.as_bytes().to_vec()),
+        ).await?;
+        println!("Sent source msg: {}", msg_link);
+        prev_msg_link = msg_link;
+    }
+
+    // The resumed bridge only mirrors what it hasn't seen before
+    let mut bridge = Bridge::resume(
+        CHECKPOINT_PATH,
+        DEFAULT_PASSWORD,
+        transport.clone(),
+        transport.clone(),
+    ).await?;
+    if let Some((source_link, msg_link)) = bridge.recovered() {
+        println!("Mirrored {} to {} before stopping", source_link, msg_link);
+    }
+    let mirrored = bridge.run(mirror_public).await?;
+    print_mirrored(&mirrored);
+    ensure!(
+        mirrored.len() == 2,
+        "Expected 2 messages to be mirrored after resuming, mirrored {}", mirrored.len()
+    );
+    let mirrored = bridge.run(mirror_public).await?;
+    ensure!(mirrored.is_empty(), "Bridge mirrored {} messages a second time", mirrored.len());
+
+    // -----------------------------------------------------------------------------
+    // Anyone with the destination announcement can read the mirrored messages
+    let mut public_reader = Subscriber::new("PublicReader", transport);
+    public_reader.receive_announcement(&destination_announcement).await?;
+    println!("\nVerifying message retrieval: PublicReader");
+    verify_messages(&["Hello", "From", "The", "Bridge"], public_reader.fetch_all_next_msgs().await)?;
+
+    Ok(())
+}
+
+fn print_mirrored(mirrored: &[(Address, Address)]) {
+    for (source_link, msg_link) in mirrored {
+        println!("Mirrored {} to {}", source_link, msg_link);
+    }
+}
+
+// Mirror only the messages marked as public, stripping the marker
+fn mirror_public(msg: &UnwrappedMessage) -> Option<(Bytes, Bytes)> {
+    match &msg.body {
+        MessageContent::SignedPacket { masked_payload, .. } => {
+            let text = String::from_utf8_lossy(&masked_payload.0);
+            text.strip_prefix(PUBLIC_PREFIX)
+                .map(|public| (Bytes::default(), Bytes(public.as_bytes().to_vec())))
+        }
+        _ => None,
+    }
+}
@@ -0,0 +1,111 @@
+use iota_streams::{
+    app_channels::api::{
+        psk_from_seed,
+        pskid_from_psk,
+        tangle::{Address, Author, Bytes, ChannelType, PublicKey, Subscriber, Transport},
+    },
+    core::{println, Result},
+};
+
+use crate::cli::DEFAULT_PASSWORD;
+use crate::examples::generate_seed;
+use crate::tools::ChannelGraph;
+use rand::Rng;
+use core::str::FromStr;
+use std::path::Path;
+
+/**
+ * This example generates a small mixed access channel (as in the Mixed Access Multi Branch
+ * example), walks it as each of the subscribers and exports the resulting channel tree as
+ * Graphviz DOT to `out_path` and as Mermaid next to it. The Author's view is added by walking back
+ * from the tip of each branch. Each branch is coloured separately, every node lists the identities
+ * that were able to read it, and the sequence messages read along the way are drawn with a dashed
+ * link to the message they point to.
+ */
+pub async fn example<T: Transport>(transport: T, out_path: &Path) -> Result<()> {
+    // Generate a unique seed for the author
+    let seed: &str = &generate_seed();
+
+    // Generate an Author
+    let mut author = Author::new(seed, ChannelType::MultiBranch, transport.clone());
+
+    // Create the channel with an announcement message. Make sure to save the resulting link somewhere,
+    let announcement_link = author.send_announce().await?;
+    // This link acts as a root for the channel itself
+    let ann_link_string = announcement_link.to_string();
+    println!(
+        "Announcement Link: {}\nTangle Index: {:#}\n",
+        ann_link_string, announcement_link.to_msg_index()
+    );
+
+    // Generate a key to be used as a Pre Shared Key by Subscriber B
+    let key = rand::thread_rng().gen::<[u8; 32]>();
+    let psk = psk_from_seed(&key);
+    let pskid = pskid_from_psk(&psk);
+    author.store_psk(pskid, psk)?;
+
+    // ------------------------------------------------------------------
+    // In their own separate instances generate the subscriber(s) that will be attaching to the channel
+    let mut subscriber_a = Subscriber::new("SubscriberA", transport.clone());
+    let mut subscriber_b = Subscriber::new("SubscriberB", transport.clone());
+    let mut subscriber_c = Subscriber::new("SubscriberC", transport.clone());
+
+    let ann_address = Address::from_str(&ann_link_string)?;
+    subscriber_a.receive_announcement(&ann_address).await?;
+    subscriber_b.receive_announcement(&ann_address).await?;
+    subscriber_c.receive_announcement(&ann_address).await?;
+
+    // Sub A subscribes traditionally, Sub B uses the PSK and Sub C does not subscribe at all
+    let subscribe_msg_a = subscriber_a.send_subscribe(&ann_address).await?;
+    let sub_a_pk = subscriber_a.get_public_key().as_bytes();
+    subscriber_b.store_psk(pskid, psk)?;
+    // ----------------------------------------------------------------------
+
+    author.receive_subscribe(&subscribe_msg_a).await?;
+
+    // One private branch per subscriber, plus a public branch from the announcement
+    let (keyload_a_link, _seq) = author.send_keyload(
+        &announcement_link,
+        &vec![PublicKey::from_bytes(sub_a_pk)?.into()],
+    ).await?;
+    let (keyload_b_link, _seq) = author.send_keyload(&announcement_link, &vec![pskid.into()]).await?;
+
+    let mut tips = Vec::new();
+    for (branch_root, label) in &[
+        (keyload_a_link, "Subscriber A"),
+        (keyload_b_link, "Subscriber B"),
+        (announcement_link.clone(), "Anyone"),
+    ] {
+        let mut prev_msg_link = branch_root.clone();
+        for input in &["Branch", "For", *label] {
+            let (msg_link, _seq_link) = author.send_signed_packet(
+                &prev_msg_link,
+                &Bytes::default(),
+                &Bytes(input.as_bytes().to_vec()),
+            ).await?;
+            println!("Sent msg for {}: {}, tangle index: {:#}", label, msg_link, msg_link.to_msg_index());
+            prev_msg_link = msg_link;
+        }
+        tips.push(prev_msg_link);
+    }
+
+    // -----------------------------------------------------------------------------
+    // Walk the channel as each subscriber to build up the tree and who can read each node
+    let mut graph = ChannelGraph::new(&announcement_link);
+    graph.walk("SubscriberA", &mut subscriber_a).await;
+    graph.walk("SubscriberB", &mut subscriber_b).await;
+    graph.walk("SubscriberC", &mut subscriber_c).await;
+    // The Author's own messages are never returned by fetching, so its view is walked back from
+    // the tip of each branch, through a copy of it so the Author itself is left untouched
+    let mut author_copy =
+        Author::import(&author.export(DEFAULT_PASSWORD).await?, DEFAULT_PASSWORD, transport).await?;
+    graph.walk_back("Author", &mut author_copy, &tips).await?;
+    println!("\nChannel tree contains {} messages", graph.nodes().len());
+
+    let (dot_path, mermaid_path) = (out_path.with_extension("dot"), out_path.with_extension("mmd"));
+    graph.write(&dot_path)?;
+    graph.write(&mermaid_path)?;
+    println!("Channel tree written to {} and {}", dot_path.display(), mermaid_path.display());
+
+    Ok(())
+}
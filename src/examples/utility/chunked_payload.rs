@@ -0,0 +1,109 @@
+use anyhow::{anyhow, ensure};
+use iota_streams::{
+    app_channels::api::tangle::{Author, ChannelType, Subscriber, Transport},
+    core::{println, Result},
+};
+
+use crate::examples::{expect_failure, generate_seed};
+use crate::tools::{chunking::DEFAULT_CHUNK_SIZE, publish_chunked, receive_chunked, Manifest};
+use crate::transport::PrefetchTransport;
+use rand::Rng;
+
+// Size of the generated file, spanning several chunks with a partial one at the end
+const PAYLOAD_SIZE: usize = 10 * DEFAULT_CHUNK_SIZE + 300;
+// Chunks small enough that their links don't fit in a single manifest message
+const SMALL_CHUNK_SIZE: usize = DEFAULT_CHUNK_SIZE / 4;
+const CONCURRENCY: usize = 4;
+
+/**
+ * This example publishes a file larger than a single message can hold in a private Single Branch
+ * channel. The payload is split into chunks published as a chain of signed packets, followed by a
+ * manifest holding the hash, size and links of the chunks. Subscriber A reassembles the file from
+ * the messages it syncs, while Subscriber B fetches it straight from the manifest link, fetching
+ * the chunks several at a time. The file is then published again in smaller chunks, whose links
+ * are published in pages ahead of the manifest, and a manifest with its chunks out of order is
+ * shown to fail verification.
+ */
+pub async fn example<T: Transport>(transport: T) -> Result<()> {
+    // Generate a unique seed for the author
+    let seed: &str = &generate_seed();
+
+    // Generate an Author
+    let mut author = Author::new(seed, ChannelType::SingleBranch, transport.clone());
+    let announcement_link = author.send_announce().await?;
+    println!(
+        "Announcement Link: {}\nTangle Index: {:#}\n",
+        announcement_link, announcement_link.to_msg_index()
+    );
+
+    let mut subscriber_a = Subscriber::new("SubscriberA", transport.clone());
+    // Subscriber B reads over a transport that prefetches the chunks
+    let prefetch = PrefetchTransport::new(transport);
+    let mut subscriber_b = Subscriber::new("SubscriberB", prefetch.clone());
+    for subscriber in [&mut subscriber_a, &mut subscriber_b] {
+        subscriber.receive_announcement(&announcement_link).await?;
+        let subscribe_msg = subscriber.send_subscribe(&announcement_link).await?;
+        author.receive_subscribe(&subscribe_msg).await?;
+    }
+    let (keyload_link, _seq) = author.send_keyload_for_everyone(&announcement_link).await?;
+
+    // A file of random bytes
+    let payload = (0..PAYLOAD_SIZE)
+        .map(|_| rand::thread_rng().gen::<u8>())
+        .collect::<Vec<u8>>();
+    let (manifest_link, _seq_link) =
+        publish_chunked(&mut author, &keyload_link, &payload, DEFAULT_CHUNK_SIZE).await?;
+    println!(
+        "Published {} bytes in chunks of {}, manifest: {}\n",
+        payload.len(), DEFAULT_CHUNK_SIZE, manifest_link
+    );
+
+    // -----------------------------------------------------------------------------
+    // Subscriber A syncs as usual, and spots the manifest among the messages it reads
+    let msgs = subscriber_a.fetch_all_next_msgs().await;
+    let manifest = msgs
+        .iter()
+        .find_map(Manifest::from_message)
+        .ok_or_else(|| anyhow!("SubscriberA found no manifest"))?;
+    println!(
+        "SubscriberA found a manifest for {} bytes in {} chunks, hash {}",
+        manifest.size, manifest.chunk_count, manifest.hash
+    );
+    let reassembled = manifest.assemble(&msgs)?;
+    ensure!(reassembled == payload, "SubscriberA reassembled a different payload");
+    println!("SubscriberA reassembled and verified the payload");
+
+    // Subscriber B only needs the manifest link
+    let reassembled =
+        receive_chunked(&mut subscriber_b, &prefetch, &manifest_link, CONCURRENCY).await?;
+    ensure!(reassembled == payload, "SubscriberB reassembled a different payload");
+    println!("SubscriberB fetched and verified the payload from the manifest link\n");
+
+    // -----------------------------------------------------------------------------
+    // In smaller chunks, the chunk links are published in pages ahead of the manifest
+    let (paged_manifest_link, _seq_link) =
+        publish_chunked(&mut author, &manifest_link, &payload, SMALL_CHUNK_SIZE).await?;
+    let paged_msgs = subscriber_a.fetch_all_next_msgs().await;
+    let paged_manifest = paged_msgs
+        .iter()
+        .find_map(Manifest::from_message)
+        .ok_or_else(|| anyhow!("SubscriberA found no paged manifest"))?;
+    ensure!(paged_manifest.last_page.is_some(), "Expected the chunk links to be paged");
+    println!(
+        "Published the payload again in {} chunks of {}, with the chunk links in pages",
+        paged_manifest.chunk_count, SMALL_CHUNK_SIZE
+    );
+    let reassembled = paged_manifest.assemble(&paged_msgs)?;
+    ensure!(reassembled == payload, "SubscriberA reassembled a different paged payload");
+    let reassembled =
+        receive_chunked(&mut subscriber_b, &prefetch, &paged_manifest_link, CONCURRENCY).await?;
+    ensure!(reassembled == payload, "SubscriberB reassembled a different paged payload");
+    println!("Both subscribers reassembled and verified the paged payload\n");
+
+    // Chunks put back together in the wrong order don't match the manifest hash
+    let mut reordered = manifest.clone();
+    reordered.chunk_links.swap(0, 1);
+    expect_failure("Reassembling chunks out of order", reordered.assemble(&msgs))?;
+
+    Ok(())
+}
@@ -0,0 +1,159 @@
+use anyhow::ensure;
+use iota_streams::{
+    app_channels::api::tangle::{
+        Author, Bytes, ChannelType, MessageContent, Subscriber, Transport, UnwrappedMessage,
+    },
+    core::{println, Result},
+};
+
+use crate::examples::{expect_failure, generate_seed};
+use crate::tools::{
+    compression::{decompress, is_compressed, COMPRESSION_TAG},
+    ChannelReader, ChannelWriter, Codec, CompressingWriter, DecompressingReader,
+};
+use rand::Rng;
+
+// Readings batched into each published payload
+const READINGS_PER_PAYLOAD: usize = 20;
+// A codec id this build doesn't know, standing in for one added by a newer publisher
+const UNKNOWN_CODEC_ID: u8 = 9;
+
+/**
+ * This example publishes batches of sensor readings as JSON, compressed on their way into a private
+ * Single Branch channel. The Author publishes through a `CompressingWriter`, first with zstd and
+ * then with deflate, and each packet carries a small header in its public payload naming the codec
+ * of its masked payload. Subscriber A reads through a `DecompressingReader` and gets the original
+ * JSON back whichever codec was used, while Subscriber B, reading raw bytes, spots the compression
+ * header and skips those payloads.
+ *
+ * A raw binary payload is then published uncompressed, which Subscriber A reads as it is whatever
+ * its bytes, followed by a payload compressed with a codec Subscriber A doesn't know, which it
+ * leaves compressed for the caller to skip rather than failing the whole fetch.
+ */
+pub async fn example<T: Transport>(transport: T) -> Result<()> {
+    // Generate a unique seed for the author
+    let seed: &str = &generate_seed();
+
+    // Generate an Author
+    let mut author = Author::new(seed, ChannelType::SingleBranch, transport.clone());
+    let announcement_link = author.send_announce().await?;
+    println!(
+        "Announcement Link: {}\nTangle Index: {:#}\n",
+        announcement_link, announcement_link.to_msg_index()
+    );
+
+    let mut subscriber_a = Subscriber::new("SubscriberA", transport.clone());
+    let mut subscriber_b = Subscriber::new("SubscriberB", transport);
+    for subscriber in [&mut subscriber_a, &mut subscriber_b] {
+        subscriber.receive_announcement(&announcement_link).await?;
+        let subscribe_msg = subscriber.send_subscribe(&announcement_link).await?;
+        author.receive_subscribe(&subscribe_msg).await?;
+    }
+    let (keyload_link, _seq) = author.send_keyload_for_everyone(&announcement_link).await?;
+
+    // -----------------------------------------------------------------------------
+    // Repetitive JSON, the kind of payload that compresses well
+    let payloads = (0..4).map(sensor_readings).collect::<Vec<String>>();
+    let mut prev_link = keyload_link;
+    for (i, payload) in payloads.iter().enumerate() {
+        let codec = if i < 2 { Codec::Zstd } else { Codec::Deflate };
+        let mut writer = CompressingWriter::new(&mut author, codec);
+        let (msg_link, _seq) = writer.send_signed_packet(
+            &prev_link,
+            &Bytes::default(),
+            &Bytes(payload.as_bytes().to_vec()),
+        ).await?;
+        println!(
+            "Sent {} bytes of readings compressed with {}: {}",
+            payload.len(), codec, msg_link
+        );
+        prev_link = msg_link;
+    }
+
+    // -----------------------------------------------------------------------------
+    println!("\nSubscriberA reads through a DecompressingReader");
+    let msgs = DecompressingReader::new(&mut subscriber_a).fetch_all_next_msgs().await;
+    let received = msgs.iter().filter_map(packet_payloads).collect::<Vec<(&[u8], &[u8])>>();
+    ensure!(received.len() == payloads.len(), "SubscriberA read {} payloads", received.len());
+    for ((public_payload, masked_payload), sent) in received.iter().zip(&payloads) {
+        ensure!(public_payload.is_empty(), "SubscriberA read a public payload that wasn't sent");
+        ensure!(*masked_payload == sent.as_bytes(), "SubscriberA decoded a different payload");
+    }
+    println!("SubscriberA decoded all {} payloads back to the original JSON", payloads.len());
+
+    println!("\nSubscriberB reads raw bytes");
+    let msgs = subscriber_b.fetch_all_next_msgs().await;
+    let mut skipped = 0;
+    for (public_payload, masked_payload) in msgs.iter().filter_map(packet_payloads) {
+        if is_compressed(public_payload) {
+            println!("SubscriberB skips a compressed payload of {} bytes", masked_payload.len());
+            skipped += 1;
+        }
+    }
+    ensure!(skipped == payloads.len(), "SubscriberB skipped {} payloads", skipped);
+
+    // -----------------------------------------------------------------------------
+    // Raw binary published uncompressed, starting with bytes that look like a codec header
+    let binary = vec![0xFF, b'S', b'Z', Codec::Zstd.id(), 0x28, 0xB5, 0x2F, 0xFD];
+    let (msg_link, _seq) = author.send_signed_packet(
+        &prev_link,
+        &Bytes::default(),
+        &Bytes(binary.clone()),
+    ).await?;
+    println!("\nSent {} bytes of raw binary: {}", binary.len(), msg_link);
+    prev_link = msg_link;
+
+    let msgs = DecompressingReader::new(&mut subscriber_a).fetch_all_next_msgs().await;
+    let (_public_payload, masked_payload) = msgs
+        .iter()
+        .find_map(packet_payloads)
+        .expect("SubscriberA should have read the packet");
+    ensure!(masked_payload == binary.as_slice(), "The raw binary was taken as compressed");
+    println!("SubscriberA read the raw binary as it was sent");
+
+    // A payload behind a header this build doesn't recognise
+    let mut unknown = COMPRESSION_TAG.to_vec();
+    unknown.push(UNKNOWN_CODEC_ID);
+    let (msg_link, _seq) = author.send_signed_packet(
+        &prev_link,
+        &Bytes(unknown),
+        &Bytes(payloads[0].as_bytes().to_vec()),
+    ).await?;
+    println!("\nSent a payload compressed with an unknown codec: {}", msg_link);
+
+    let msgs = DecompressingReader::new(&mut subscriber_a).fetch_all_next_msgs().await;
+    let (public_payload, masked_payload) = msgs
+        .iter()
+        .find_map(packet_payloads)
+        .expect("SubscriberA should have read the packet");
+    ensure!(is_compressed(public_payload), "The unknown codec was decoded");
+    println!("SubscriberA left the payload compressed and skips it");
+    expect_failure("Decompressing an unknown codec", decompress(public_payload, masked_payload))?;
+
+    Ok(())
+}
+
+// A batch of readings from a single sensor, serialised as JSON
+fn sensor_readings(batch: usize) -> String {
+    let readings = (0..READINGS_PER_PAYLOAD)
+        .map(|i| {
+            let temperature = 20.0 + rand::thread_rng().gen_range(0.0, 5.0);
+            let humidity = 45.0 + rand::thread_rng().gen_range(0.0, 10.0);
+            format!(
+                "{{\"sensor\":\"gh-1\",\"seq\":{},\"temperature\":{:.1},\"humidity\":{:.1}}}",
+                batch * READINGS_PER_PAYLOAD + i, temperature, humidity
+            )
+        })
+        .collect::<Vec<String>>();
+    format!("[{}]", readings.join(","))
+}
+
+// The public and masked payloads of a signed packet
+fn packet_payloads(msg: &UnwrappedMessage) -> Option<(&[u8], &[u8])> {
+    match &msg.body {
+        MessageContent::SignedPacket { public_payload, masked_payload, .. } => {
+            Some((&public_payload.0, &masked_payload.0))
+        }
+        _ => None,
+    }
+}
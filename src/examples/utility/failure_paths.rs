@@ -0,0 +1,245 @@
+use iota_streams::{
+    app_channels::api::tangle::{
+        Address, Author, Bytes, ChannelType, PublicKey, Subscriber, Transport,
+    },
+    core::{println, Result},
+};
+
+use crate::cli::DEFAULT_PASSWORD;
+use crate::examples::{expect_error, generate_seed};
+use rand::Rng;
+use core::str::FromStr;
+
+// Fragments one of which each error is expected to mention. Streams reports a link missing from a
+// user's store and a link missing from the transport alike as not found.
+const LINK_NOT_FOUND: &[&str] = &["not found"];
+const BAD_STATE_PASSWORD: &[&str] = &["mac", "integrity", "decrypt", "password"];
+const NO_ACCESS: &[&str] = &["not found", "access", "key"];
+
+/**
+ * This example walks through the paths that are expected to fail, showing the error returned by
+ * each one so that error handling can be designed against them:
+ * - A revoked subscriber trying to publish in a branch it was removed from
+ * - Importing a user state with the wrong password
+ * - A non-subscriber retrieving a message by sequence number from a private anchor
+ * - Linking to, or receiving, an address that does not exist
+ *
+ * Each case asserts that the operation fails with the error expected of it, and that the user is
+ * still usable afterwards. The same cases are run as tests by `cargo test`.
+ */
+pub async fn example<T: Transport>(transport: T) -> Result<()> {
+    println!("Revoked subscriber publishing");
+    revoked_subscriber_publishing(transport.clone()).await?;
+
+    println!("\nImporting state with the wrong password");
+    wrong_password_import(transport.clone()).await?;
+
+    println!("\nNon-subscriber reading from a private single depth anchor");
+    outsider_reading_private_anchor(transport.clone()).await?;
+
+    println!("\nLinking to an address that does not exist");
+    publishing_to_nonexistent_link(transport.clone()).await?;
+    receiving_nonexistent_announcement(transport).await?;
+
+    Ok(())
+}
+
+// A subscriber revoked by a later keyload trying to publish in the new branch
+pub async fn revoked_subscriber_publishing<T: Transport>(transport: T) -> Result<String> {
+    let mut author = Author::new(&generate_seed(), ChannelType::MultiBranch, transport.clone());
+    let announcement_link = author.send_announce().await?;
+
+    let mut subscriber_a = Subscriber::new("SubscriberA", transport.clone());
+    let mut subscriber_b = Subscriber::new("SubscriberB", transport);
+    subscriber_a.receive_announcement(&announcement_link).await?;
+    subscriber_b.receive_announcement(&announcement_link).await?;
+    let subscribe_msg_a = subscriber_a.send_subscribe(&announcement_link).await?;
+    let subscribe_msg_b = subscriber_b.send_subscribe(&announcement_link).await?;
+    let sub_a_pk = subscriber_a.get_public_key().as_bytes();
+    let sub_b_pk = subscriber_b.get_public_key().as_bytes();
+    author.receive_subscribe(&subscribe_msg_a).await?;
+    author.receive_subscribe(&subscribe_msg_b).await?;
+
+    // Both subscribers are granted access to the first branch
+    let (keyload_link, _seq) = author.send_keyload(
+        &announcement_link,
+        &vec![PublicKey::from_bytes(sub_a_pk)?.into(), PublicKey::from_bytes(sub_b_pk)?.into()],
+    ).await?;
+    let (msg_link, seq_link) = author.send_signed_packet(
+        &keyload_link,
+        &Bytes::default(),
+        &Bytes("Readable by A and B".as_bytes().to_vec()),
+    ).await?;
+    println!("Sent msg for Sub A and B: {}", msg_link);
+    let seq_link = seq_link.unwrap();
+
+    // Subscriber A is revoked by a keyload attached to the sequence message of the previous branch
+    let (revoking_keyload_link, _seq) = author.send_keyload(
+        &seq_link,
+        &vec![PublicKey::from_bytes(sub_b_pk)?.into()],
+    ).await?;
+    println!("Sent Keyload revoking Sub A: {}", revoking_keyload_link);
+
+    subscriber_a.sync_state().await;
+    let error = expect_error(
+        "Subscriber A publishing in the branch it was revoked from",
+        subscriber_a.send_signed_packet(
+            &revoking_keyload_link,
+            &Bytes::default(),
+            &Bytes("Should not be published".as_bytes().to_vec()),
+        ).await,
+        NO_ACCESS,
+    )?;
+    // Subscriber B is still allowed to publish in the branch
+    subscriber_b.sync_state().await;
+    subscriber_b.send_signed_packet(
+        &revoking_keyload_link,
+        &Bytes::default(),
+        &Bytes("Published by B".as_bytes().to_vec()),
+    ).await?;
+    Ok(error)
+}
+
+// An exported user state imported with a different password than it was exported with
+pub async fn wrong_password_import<T: Transport>(transport: T) -> Result<String> {
+    let mut author = Author::new(&generate_seed(), ChannelType::SingleBranch, transport.clone());
+    let announcement_link = author.send_announce().await?;
+    let mut subscriber = Subscriber::new("Subscriber", transport.clone());
+    subscriber.receive_announcement(&announcement_link).await?;
+
+    let state = subscriber.export(DEFAULT_PASSWORD).await?;
+    let error = expect_error(
+        "Importing a Subscriber with the wrong password",
+        Subscriber::import(&state, "WrongPassword", transport.clone()).await,
+        BAD_STATE_PASSWORD,
+    )?;
+    Subscriber::import(&state, DEFAULT_PASSWORD, transport).await?;
+    Ok(error)
+}
+
+// A subscriber left out of the keyload of a single depth anchor reading from it by sequence number
+pub async fn outsider_reading_private_anchor<T: Transport>(transport: T) -> Result<String> {
+    let mut author = Author::new(&generate_seed(), ChannelType::SingleDepth, transport.clone());
+    let announcement_link = author.send_announce().await?;
+
+    let mut member = Subscriber::new("SingleDepthMember", transport.clone());
+    let mut outsider = Subscriber::new("SingleDepthOutsider", transport);
+    member.receive_announcement(&announcement_link).await?;
+    outsider.receive_announcement(&announcement_link).await?;
+    let member_subscribe_link = member.send_subscribe(&announcement_link).await?;
+    author.receive_subscribe(&member_subscribe_link).await?;
+
+    let (anchor_link, _seq) = author.send_keyload_for_everyone(&announcement_link).await?;
+    author.send_signed_packet(
+        &anchor_link,
+        &Bytes::default(),
+        &Bytes("Only for members".as_bytes().to_vec()),
+    ).await?;
+    let error = expect_error(
+        "Outsider retrieving message 0 from the private anchor",
+        outsider.receive_msg_by_sequence_number(&anchor_link, 0).await,
+        NO_ACCESS,
+    )?;
+    member.receive_msg_by_sequence_number(&anchor_link, 0).await?;
+    Ok(error)
+}
+
+// The Author publishing a packet linked to an address with no message behind it
+pub async fn publishing_to_nonexistent_link<T: Transport>(transport: T) -> Result<String> {
+    let mut author = Author::new(&generate_seed(), ChannelType::SingleBranch, transport);
+    let announcement_link = author.send_announce().await?;
+
+    let missing_link = nonexistent_link(&announcement_link)?;
+    let error = expect_error(
+        "Author publishing a packet linked to a nonexistent address",
+        author.send_signed_packet(
+            &missing_link,
+            &Bytes::default(),
+            &Bytes("Linked to nowhere".as_bytes().to_vec()),
+        ).await,
+        LINK_NOT_FOUND,
+    )?;
+    // The Author can still publish to the real channel
+    author.send_signed_packet(
+        &announcement_link,
+        &Bytes::default(),
+        &Bytes("Linked to the announcement".as_bytes().to_vec()),
+    ).await?;
+    Ok(error)
+}
+
+// A subscriber receiving the announcement from an address with no message behind it
+pub async fn receiving_nonexistent_announcement<T: Transport>(transport: T) -> Result<String> {
+    let mut author = Author::new(&generate_seed(), ChannelType::SingleBranch, transport.clone());
+    let announcement_link = author.send_announce().await?;
+
+    let mut subscriber = Subscriber::new("LateSubscriber", transport);
+    let error = expect_error(
+        "Subscriber receiving an announcement from a nonexistent address",
+        subscriber.receive_announcement(&nonexistent_link(&announcement_link)?).await,
+        LINK_NOT_FOUND,
+    )?;
+    subscriber.receive_announcement(&announcement_link).await?;
+    Ok(error)
+}
+
+// An address in the same channel with a random message id
+fn nonexistent_link(announcement_link: &Address) -> Result<Address> {
+    let ann_link_string = announcement_link.to_string();
+    let (appinst, msgid) = ann_link_string.split_once(':').unwrap_or((&ann_link_string, ""));
+    let random_msgid = (0..msgid.len())
+        .map(|_| format!("{:x}", rand::thread_rng().gen_range(0, 16)))
+        .collect::<String>();
+    Ok(Address::from_str(&format!("{}:{}", appinst, random_msgid))?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::transport::MemoryTransport;
+    use futures::executor::block_on;
+
+    fn offline_transport() -> MemoryTransport {
+        MemoryTransport::new()
+    }
+
+    // Each path has already checked its error with `expect_error`, this restates the expectation
+    // where the test reads it
+    fn assert_mentions(error: &str, fragments: &[&str]) {
+        let lowercase = error.to_lowercase();
+        assert!(
+            fragments.iter().any(|fragment| lowercase.contains(fragment)),
+            "Expected an error mentioning one of {:?}, got: {}", fragments, error
+        );
+    }
+
+    #[test]
+    fn revoked_subscriber_cannot_publish() {
+        let error = block_on(revoked_subscriber_publishing(offline_transport())).unwrap();
+        assert_mentions(&error, NO_ACCESS);
+    }
+
+    #[test]
+    fn wrong_password_fails_import() {
+        let error = block_on(wrong_password_import(offline_transport())).unwrap();
+        assert_mentions(&error, BAD_STATE_PASSWORD);
+    }
+
+    #[test]
+    fn outsider_cannot_read_private_anchor() {
+        let error = block_on(outsider_reading_private_anchor(offline_transport())).unwrap();
+        assert_mentions(&error, NO_ACCESS);
+    }
+
+    #[test]
+    fn publishing_to_nonexistent_link_reports_it_missing() {
+        let error = block_on(publishing_to_nonexistent_link(offline_transport())).unwrap();
+        assert_mentions(&error, LINK_NOT_FOUND);
+    }
+
+    #[test]
+    fn receiving_nonexistent_announcement_reports_it_missing() {
+        let error = block_on(receiving_nonexistent_announcement(offline_transport())).unwrap();
+        assert_mentions(&error, LINK_NOT_FOUND);
+    }
+}
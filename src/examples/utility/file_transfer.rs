@@ -0,0 +1,132 @@
+use anyhow::ensure;
+use iota_streams::{
+    app_channels::api::tangle::{Author, ChannelType, Subscriber},
+    core::{println, Result},
+};
+
+use crate::cli::DEFAULT_PASSWORD;
+use crate::examples::{expect_failure, generate_seed};
+use crate::tools::{FileReceiver, FileSender};
+use crate::transport::{MemoryTransport, StandInNode};
+use rand::Rng;
+use std::{fs, path::Path};
+
+const SEND_STATE_PATH: &str = "./transfer_send.json";
+const RECEIVE_STATE_PATH: &str = "./transfer_receive.json";
+const OUTPUT_PATH: &str = "./received_file.bin";
+const FILE_SIZE: usize = 6000;
+const CHUNK_SIZE: usize = 500;
+// The node goes down once this many chunks have been sent
+const CHUNKS_BEFORE_OUTAGE: usize = 5;
+
+/**
+ * This example transfers a file over a private Single Branch channel, with both sides crashing part
+ * way through. The Author streams the file in chunks with a `FileSender`, and the node goes down
+ * after a few of them, failing the transfer. Subscriber A receives what was sent so far with a
+ * `FileReceiver` and then stops. Both sides then restart from their persisted transfer state: the
+ * Author carries on from the first unsent chunk, and Subscriber A from the first chunk it hadn't
+ * read, before reassembling the file and verifying it against the manifest.
+ */
+pub async fn example() -> Result<()> {
+    // Generate a unique seed for the author
+    let seed: &str = &generate_seed();
+
+    for path in &[SEND_STATE_PATH, RECEIVE_STATE_PATH, OUTPUT_PATH] {
+        if Path::new(path).exists() {
+            fs::remove_file(path)?;
+        }
+    }
+
+    let node = StandInNode::new(MemoryTransport::new());
+
+    // Generate an Author
+    let mut author = Author::new(seed, ChannelType::SingleBranch, node.clone());
+    let announcement_link = author.send_announce().await?;
+    println!(
+        "Announcement Link: {}\nTangle Index: {:#}\n",
+        announcement_link, announcement_link.to_msg_index()
+    );
+
+    let mut subscriber_a = Subscriber::new("SubscriberA", node.clone());
+    subscriber_a.receive_announcement(&announcement_link).await?;
+    let subscribe_msg = subscriber_a.send_subscribe(&announcement_link).await?;
+    author.receive_subscribe(&subscribe_msg).await?;
+    let (keyload_link, _seq) = author.send_keyload_for_everyone(&announcement_link).await?;
+
+    let file = (0..FILE_SIZE)
+        .map(|_| rand::thread_rng().gen::<u8>())
+        .collect::<Vec<u8>>();
+
+    // -----------------------------------------------------------------------------
+    println!("Author starts sending {} bytes in chunks of {}", FILE_SIZE, CHUNK_SIZE);
+    let mut sender =
+        FileSender::new(SEND_STATE_PATH, "file.bin", &file, &keyload_link, CHUNK_SIZE)?;
+    let outage = node.clone();
+    let result = sender.send(&mut author, &file, DEFAULT_PASSWORD, |progress| {
+        println!("Sent chunk {}/{}", progress.chunks_done, progress.chunk_count);
+        if progress.chunks_done == CHUNKS_BEFORE_OUTAGE {
+            outage.go_down();
+        }
+    }).await;
+    expect_failure("Sending while the node is down", result)?;
+    // The Author process crashes, and the node comes back up
+    drop(sender);
+    drop(author);
+    node.come_up();
+
+    println!("\nSubscriberA receives what has been sent so far");
+    let mut receiver = FileReceiver::open(RECEIVE_STATE_PATH, OUTPUT_PATH)?;
+    let complete = receiver.receive(&mut subscriber_a, DEFAULT_PASSWORD, |progress| {
+        println!("Received {}/{} bytes", progress.bytes_done, progress.size);
+    }).await?;
+    ensure!(!complete, "The transfer was complete before the Author finished sending");
+    // The Subscriber process stops as well
+    drop(receiver);
+    drop(subscriber_a);
+
+    // -----------------------------------------------------------------------------
+    println!("\nAuthor restarts and resumes the transfer");
+    let mut sender = FileSender::open(SEND_STATE_PATH)?;
+    let mut author = sender
+        .author(DEFAULT_PASSWORD, node.clone())
+        .await?
+        .expect("The Author state was persisted with the first message sent");
+    let mut resumed_from = None;
+    let manifest_link = sender.send(&mut author, &file, DEFAULT_PASSWORD, |progress| {
+        if resumed_from.is_none() {
+            resumed_from = Some(progress.chunks_done);
+        }
+        println!("Sent chunk {}/{}", progress.chunks_done, progress.chunk_count);
+    }).await?;
+    println!("Sent manifest: {}", manifest_link);
+    ensure!(
+        resumed_from == Some(CHUNKS_BEFORE_OUTAGE + 1),
+        "Sending resumed from chunk {:?}", resumed_from
+    );
+
+    println!("\nSubscriberA restarts and resumes the transfer");
+    let mut receiver = FileReceiver::open(RECEIVE_STATE_PATH, OUTPUT_PATH)?;
+    let mut subscriber_a = receiver
+        .subscriber(DEFAULT_PASSWORD, node)
+        .await?
+        .expect("The Subscriber state was persisted with the first round read");
+    let already_received = receiver.progress().map_or(0, |progress| progress.chunks_done);
+    let mut resumed_from = None;
+    let complete = receiver.receive(&mut subscriber_a, DEFAULT_PASSWORD, |progress| {
+        if resumed_from.is_none() {
+            resumed_from = Some(progress.chunks_done);
+        }
+        println!("Received {}/{} bytes", progress.bytes_done, progress.size);
+    }).await?;
+    ensure!(complete, "The transfer was not completed");
+    ensure!(
+        resumed_from == Some(already_received + 1),
+        "Receiving resumed from chunk {:?} after {} had been received",
+        resumed_from, already_received
+    );
+
+    ensure!(fs::read(OUTPUT_PATH)? == file, "The received file differs from the one sent");
+    println!("\nSubscriberA received the whole file and verified it against the manifest");
+
+    Ok(())
+}
@@ -0,0 +1,125 @@
+use anyhow::ensure;
+use futures::StreamExt;
+use iota_streams::{
+    app_channels::api::tangle::{Author, Bytes, ChannelType, Subscriber, Transport},
+    core::{println, Result},
+};
+
+use crate::examples::{expect_failure, generate_seed, verify_messages};
+use crate::tools::{BackwardPages, ForwardPages};
+
+const MSG_COUNT: usize = 25;
+const PAGE_SIZE: usize = 10;
+
+/**
+ * This example pages through a long chain of messages in a private Single Branch channel without
+ * loading it all at once. SubscriberA walks backwards from the latest message a page at a time,
+ * newest first, stopping at the keyload the chain was linked from. SubscriberB then walks forwards
+ * from a message in the middle of the chain, failing when asked to start after a message it has
+ * already read, and a recovered reader walks the whole channel as a stream of pages.
+ */
+pub async fn example<T: Transport>(transport: T) -> Result<()> {
+    // Generate a unique seed for the author
+    let seed: &str = &generate_seed();
+
+    // Generate an Author
+    let mut author = Author::new(seed, ChannelType::SingleBranch, transport.clone());
+    let announcement_link = author.send_announce().await?;
+    println!(
+        "Announcement Link: {}\nTangle Index: {:#}\n",
+        announcement_link, announcement_link.to_msg_index()
+    );
+
+    let mut subscriber_a = Subscriber::new("SubscriberA", transport.clone());
+    let mut subscriber_b = Subscriber::new("SubscriberB", transport.clone());
+    for subscriber in [&mut subscriber_a, &mut subscriber_b] {
+        subscriber.receive_announcement(&announcement_link).await?;
+        let subscribe_msg = subscriber.send_subscribe(&announcement_link).await?;
+        author.receive_subscribe(&subscribe_msg).await?;
+    }
+    let (keyload_link, _seq) = author.send_keyload_for_everyone(&announcement_link).await?;
+    println!("Sent keyload: {}", keyload_link);
+
+    // Author publishes a chain of messages long enough to span several pages
+    let msg_inputs = (0..MSG_COUNT)
+        .map(|i| format!("Message {}", i))
+        .collect::<Vec<String>>();
+    let mut msg_links = Vec::new();
+    let mut prev_msg_link = keyload_link.clone();
+    for input in &msg_inputs {
+        let (msg_link, _seq_link) = author.send_signed_packet(
+            &prev_msg_link,
+            &Bytes::default(),
+            &Bytes(input.as_bytes().to_vec()),
+        ).await?;
+        msg_links.push(msg_link.clone());
+        prev_msg_link = msg_link;
+    }
+    println!("Sent {} msgs, last msg: {}\n", MSG_COUNT, prev_msg_link);
+
+    // -----------------------------------------------------------------------------
+    // SubscriberA pages backwards from the latest message, which itself is not part of any page.
+    // Packets are unwrapped with the keys of the keyload, so the reader syncs its state first.
+    subscriber_a.sync_state().await;
+    let mut backward = BackwardPages::new(&mut subscriber_a, &prev_msg_link, PAGE_SIZE);
+    let mut expected_end = MSG_COUNT - 1;
+    while let Some(page) = backward.next_page().await? {
+        let start = expected_end.saturating_sub(page.len());
+        println!("Backward page of {} msgs", page.len());
+        let expected = msg_inputs[start..expected_end]
+            .iter()
+            .rev()
+            .map(String::as_str)
+            .collect::<Vec<&str>>();
+        verify_messages(&expected, page)?;
+        expected_end = start;
+    }
+    ensure!(expected_end == 0, "Backward walk ended early, before message {}", expected_end);
+    ensure!(
+        backward.boundary() == Some(&keyload_link),
+        "Backward walk did not stop at the keyload"
+    );
+    println!("Backward walk stopped at keyload: {}\n", keyload_link);
+
+    // -----------------------------------------------------------------------------
+    // SubscriberB pages forwards, starting after a message in the middle of the chain
+    let middle = MSG_COUNT / 2;
+    let mut forward = ForwardPages::after(&mut subscriber_b, &msg_links[middle], PAGE_SIZE);
+    let mut expected_start = middle + 1;
+    while let Some(page) = forward.next_page().await? {
+        let end = expected_start + page.len();
+        println!("Forward page of {} msgs", page.len());
+        let expected = msg_inputs[expected_start..end]
+            .iter()
+            .map(String::as_str)
+            .collect::<Vec<&str>>();
+        verify_messages(&expected, page)?;
+        expected_start = end;
+    }
+    ensure!(expected_start == MSG_COUNT, "Forward walk ended early, at message {}", expected_start);
+
+    // SubscriberB is now past the first message, so a walk starting after it can't find it
+    expect_failure(
+        "Paging forwards after a message the reader has already read",
+        ForwardPages::after(&mut subscriber_b, &msg_links[0], PAGE_SIZE).next_page().await,
+    )?;
+
+    // The same walk as a stream of pages, from the start of the channel. A reader recovered from
+    // SubscriberA's seed reads the keyload as well as every message.
+    let mut recovered = Subscriber::new("SubscriberA", transport);
+    recovered.receive_announcement(&announcement_link).await?;
+    let page_sizes = ForwardPages::new(&mut recovered, PAGE_SIZE)
+        .into_stream()
+        .map(|page| page.map(|page| page.len()))
+        .collect::<Vec<Result<usize>>>()
+        .await
+        .into_iter()
+        .collect::<Result<Vec<usize>>>()?;
+    println!("\nStreamed pages of {:?} msgs", page_sizes);
+    ensure!(
+        page_sizes.iter().sum::<usize>() == MSG_COUNT + 1,
+        "Streamed walk did not read the keyload and every message"
+    );
+
+    Ok(())
+}
@@ -0,0 +1,107 @@
+use anyhow::ensure;
+use iota_streams::{
+    app_channels::api::tangle::{Author, Bytes, ChannelType, MessageContent, Subscriber},
+    core::{println, Result},
+};
+
+use crate::examples::generate_seed;
+use crate::tools::receive_range;
+use crate::transport::{MemoryTransport, PrefetchTransport, StandInNode};
+use std::time::{Duration, Instant};
+
+const MSG_COUNT: u32 = 12;
+const CONCURRENCY: usize = 8;
+// Round trip added to every request to the stand-in node
+const LATENCY: Duration = Duration::from_millis(10);
+
+/**
+ * This example uses a public Single Depth channel as an indexed log. Every entry is attached to the
+ * announcement, so it can be looked up by its sequence number, as in the single depth example.
+ * Two entries are then dropped from the node, and a Subscriber reads a range of sequence numbers
+ * that runs past the end of the log with `receive_range`, which fetches the whole range
+ * concurrently and reports the sequence numbers it found no entry for. A request the node fails
+ * during the read is reported separately, rather than as a gap in the log.
+ */
+pub async fn example() -> Result<()> {
+    // Generate a unique seed for the author
+    let seed: &str = &generate_seed();
+
+    let storage = MemoryTransport::new();
+    let node = StandInNode::new(storage.clone());
+
+    // Generate an Author
+    let mut author = Author::new(seed, ChannelType::SingleDepth, node.clone());
+    let announcement_link = author.send_announce().await?;
+    println!(
+        "Announcement Link: {}\nTangle Index: {:#}\n",
+        announcement_link, announcement_link.to_msg_index()
+    );
+
+    // Every entry of the log is anchored to the announcement
+    let anchor_msg_link = announcement_link.clone();
+    let mut entry_links = Vec::new();
+    for i in 0..MSG_COUNT {
+        let (msg_link, _seq_link) = author.send_signed_packet(
+            &anchor_msg_link,
+            &Bytes::default(),
+            &Bytes(format!("Entry {}", i).into_bytes()),
+        ).await?;
+        entry_links.push(msg_link);
+    }
+    println!("Appended {} entries to the log", MSG_COUNT);
+
+    // Two entries never make it to the node
+    let dropped = vec![4, 9];
+    for &i in &dropped {
+        storage.remove(&entry_links[i as usize]);
+    }
+    println!("Dropped entries {:?} from the node\n", dropped);
+
+    // -----------------------------------------------------------------------------
+    node.set_latency(LATENCY);
+    let transport = PrefetchTransport::new(node.clone());
+    let mut subscriber_a = Subscriber::new("SubscriberA", transport.clone());
+    subscriber_a.receive_announcement(&announcement_link).await?;
+
+    // The node also fails one of the requests while reading, without the entry being missing
+    node.fail_next(1);
+    let range = 0..MSG_COUNT + 3;
+    println!("Reading entries {:?} with {} requests in flight", range, CONCURRENCY);
+    let start = Instant::now();
+    let entries = receive_range(
+        &mut subscriber_a,
+        &transport,
+        &anchor_msg_link,
+        range.clone(),
+        CONCURRENCY,
+    ).await;
+    println!("Read {} entries in {:?}", entries.messages.len(), start.elapsed());
+
+    for (msg_num, msg) in &entries.messages {
+        match &msg.body {
+            MessageContent::SignedPacket { masked_payload, .. } => {
+                let entry = String::from_utf8(masked_payload.0.clone())?;
+                ensure!(entry == format!("Entry {}", msg_num), "Entry {} read as {}", msg_num, entry);
+            }
+            _ => panic!("Not a signed packet"),
+        }
+    }
+    println!("Every entry read matches its sequence number");
+
+    // The failed request is reported apart from the gaps in the log
+    let failed = entries.failed.iter().map(|(msg_num, _e)| *msg_num).collect::<Vec<u32>>();
+    for (msg_num, e) in &entries.failed {
+        println!("Failed to fetch entry {}: {}", msg_num, e);
+    }
+    ensure!(failed.len() == 1, "Expected 1 failed request, found {:?}", failed);
+
+    // The dropped entries are reported along with those past the end of the log
+    let mut expected_missing = dropped;
+    expected_missing.extend(MSG_COUNT..range.end);
+    expected_missing.retain(|msg_num| !failed.contains(msg_num));
+    println!("Missing entries: {:?}", entries.missing);
+    ensure!(entries.missing == expected_missing, "Unexpected gaps {:?}", entries.missing);
+    ensure!(entries.unreadable.is_empty(), "Unreadable entries {:?}", entries.unreadable);
+
+    Ok(())
+}
@@ -0,0 +1,174 @@
+use anyhow::ensure;
+use iota_streams::{
+    app_channels::api::tangle::{Author, Bytes, ChannelType, PublicKey, Subscriber},
+    core::{println, Result},
+};
+
+use crate::examples::generate_seed;
+use crate::tools::{check_integrity, Issue};
+use crate::transport::MemoryTransport;
+
+/**
+ * This example runs an integrity check over two channels with problems planted in them. The first
+ * is a private Multi Branch channel, checked by an Auditor included in every keyload but the first:
+ * - The first branch is not for the Auditor, so the messages its sequence messages point to are
+ *   skipped
+ * - The keyload of the second branch is attached to the last message of the first branch rather
+ *   than its sequence message, so its previous message is unreadable to the Auditor
+ * - A message Subscriber B links to is lost from the node, breaking the chain, and its sequence
+ *   message points to nothing
+ * - A keyload is sent only to a subscriber who has since left, so no current subscriber can open it
+ *
+ * The second is a public Single Depth log with two entries lost from the node, which shows up as
+ * the sequence numbers of its anchor skipping.
+ */
+pub async fn example() -> Result<()> {
+    // Generate a unique seed for the author
+    let seed: &str = &generate_seed();
+
+    // The offline transport gives direct access to the stored messages
+    let transport = MemoryTransport::new();
+
+    // Generate an Author
+    let mut author = Author::new(seed, ChannelType::MultiBranch, transport.clone());
+    let announcement_link = author.send_announce().await?;
+    println!(
+        "Announcement Link: {}\nTangle Index: {:#}\n",
+        announcement_link, announcement_link.to_msg_index()
+    );
+
+    let mut auditor = Subscriber::new("Auditor", transport.clone());
+    let mut subscriber_a = Subscriber::new("SubscriberA", transport.clone());
+    let mut subscriber_b = Subscriber::new("SubscriberB", transport.clone());
+    let mut subscriber_c = Subscriber::new("SubscriberC", transport.clone());
+    let mut subscribe_links = Vec::new();
+    for subscriber in [&mut auditor, &mut subscriber_a, &mut subscriber_b, &mut subscriber_c] {
+        subscriber.receive_announcement(&announcement_link).await?;
+        let subscribe_msg = subscriber.send_subscribe(&announcement_link).await?;
+        author.receive_subscribe(&subscribe_msg).await?;
+        subscribe_links.push(subscribe_msg);
+    }
+    let subscriber_c_subscribe_link = &subscribe_links[3];
+    let auditor_pk = PublicKey::from_bytes(auditor.get_public_key().as_bytes())?;
+    let subscriber_a_pk = PublicKey::from_bytes(subscriber_a.get_public_key().as_bytes())?;
+    let subscriber_b_pk = PublicKey::from_bytes(subscriber_b.get_public_key().as_bytes())?;
+    let subscriber_c_pk = PublicKey::from_bytes(subscriber_c.get_public_key().as_bytes())?;
+
+    // The first branch is for Subscriber A alone, so the Auditor only reads its sequence messages
+    let mut skipped_seq_links = Vec::new();
+    let (keyload_a_link, seq_link) = author.send_keyload(
+        &announcement_link,
+        &vec![subscriber_a_pk.into()],
+    ).await?;
+    skipped_seq_links.push(seq_link.unwrap());
+    let mut prev_msg_link = keyload_a_link;
+    for input in &["Only", "For", "A"] {
+        let (msg_link, seq_link) = author.send_signed_packet(
+            &prev_msg_link,
+            &Bytes::default(),
+            &Bytes(input.as_bytes().to_vec()),
+        ).await?;
+        skipped_seq_links.push(seq_link.unwrap());
+        prev_msg_link = msg_link;
+    }
+
+    // Subscriber C leaves, but a keyload is still sent to it. This comes before the lost message,
+    // since readers can't get past a missing message of the same publisher.
+    let unsubscribe_link = subscriber_c.send_unsubscribe(subscriber_c_subscribe_link).await?;
+    author.receive_unsubscribe(&unsubscribe_link).await?;
+    let (keyload_c_link, _seq) = author.send_keyload(
+        &announcement_link,
+        &vec![subscriber_c_pk.into(), auditor_pk.into()],
+    ).await?;
+    println!("Keyload only for departed Subscriber C: {}", keyload_c_link);
+
+    // The second branch is attached to the last message of the first branch, which only
+    // Subscriber A can read
+    let (keyload_b_link, _seq) = author.send_keyload(
+        &prev_msg_link,
+        &vec![subscriber_b_pk.into(), auditor_pk.into()],
+    ).await?;
+    println!("Keyload for B attached to a message of branch A: {}", keyload_b_link);
+    let (lost_link, seq_link) = author.send_signed_packet(
+        &keyload_b_link,
+        &Bytes::default(),
+        &Bytes(b"Lost".to_vec()),
+    ).await?;
+    skipped_seq_links.push(seq_link.unwrap());
+    subscriber_b.sync_state().await;
+    let (orphan_link, _seq_link) = subscriber_b.send_signed_packet(
+        &lost_link,
+        &Bytes::default(),
+        &Bytes(b"Orphaned".to_vec()),
+    ).await?;
+    transport.remove(&lost_link);
+    println!("Lost msg {} that Subscriber B linked to\n", lost_link);
+
+    // -----------------------------------------------------------------------------
+    let current_subscribers = vec![subscriber_a, subscriber_b];
+    let report = check_integrity(
+        &announcement_link,
+        &mut auditor,
+        &mut transport.clone(),
+        &current_subscribers,
+        &[],
+    ).await?;
+    println!("Multi Branch channel: {}", report);
+
+    let mut expected: Vec<Issue> = skipped_seq_links
+        .into_iter()
+        .map(|link| Issue::SkippedMessage { link })
+        .collect();
+    expected.extend(vec![
+        Issue::UnreadablePrevious { link: keyload_b_link, prev_link: prev_msg_link },
+        Issue::BrokenChain { link: orphan_link, prev_link: lost_link },
+        Issue::UnopenableKeyload { link: keyload_c_link },
+    ]);
+    // Issues of different publishers are found in the order the walk reaches them, so the lists
+    // are compared sorted
+    let sorted = |issues: &[Issue]| {
+        let mut issues: Vec<String> = issues.iter().map(ToString::to_string).collect();
+        issues.sort();
+        issues
+    };
+    ensure!(
+        sorted(&report.issues) == sorted(&expected),
+        "Expected {:?}, found {:?}",
+        expected,
+        report.issues
+    );
+
+    // -----------------------------------------------------------------------------
+    // A public single depth log with entries anchored to its announcement
+    let log_seed = format!("{}LOG", &seed[..78]);
+    let mut log_author = Author::new(&log_seed, ChannelType::SingleDepth, transport.clone());
+    let log_link = log_author.send_announce().await?;
+    let mut entry_links = Vec::new();
+    for i in 0..6 {
+        let (msg_link, _seq_link) = log_author.send_signed_packet(
+            &log_link,
+            &Bytes::default(),
+            &Bytes(format!("Entry {}", i).into_bytes()),
+        ).await?;
+        entry_links.push(msg_link);
+    }
+    transport.remove(&entry_links[2]);
+    transport.remove(&entry_links[3]);
+
+    let mut log_reader = Subscriber::new("LogReader", transport.clone());
+    log_reader.receive_announcement(&log_link).await?;
+    let no_subscribers: Vec<Subscriber<MemoryTransport>> = Vec::new();
+    let report = check_integrity(
+        &log_link,
+        &mut log_reader,
+        &mut transport.clone(),
+        &no_subscribers,
+        &[log_link.clone()],
+    ).await?;
+    println!("Single Depth log: {}", report);
+
+    let expected = vec![Issue::SequenceSkip { anchor: log_link, missing: vec![2, 3] }];
+    ensure!(report.issues == expected, "Expected {:?}, found {:?}", expected, report.issues);
+
+    Ok(())
+}
@@ -0,0 +1,136 @@
+use anyhow::ensure;
+use iota_streams::{
+    app_channels::api::tangle::{
+        Author, Bytes, ChannelType, MessageContent, Subscriber, Transport, UnwrappedMessage,
+    },
+    core::{println, Result},
+};
+
+use crate::examples::{generate_seed, verify_messages};
+use crate::tools::rotation::{Membership, RotatingBranch, RotationPolicy};
+use std::time::Duration;
+
+/**
+ * This example keeps a long lived private branch rotating its keys. The branch is opened with a
+ * keyload for Subscribers A and B, and every few messages (or once the current keys are older than
+ * a set age) a fresh keyload for the same membership is issued on the current sequence link, the
+ * same way access is regranted in the grant and revoke example. Publishing continues on the new
+ * branch and the subscribers follow it without doing anything different, reading every message.
+ */
+pub async fn example<T: Transport>(transport: T) -> Result<()> {
+    // Generate a unique seed for the author
+    let seed: &str = &generate_seed();
+
+    // Generate an Author
+    let mut author = Author::new(seed, ChannelType::MultiBranch, transport.clone());
+    let announcement_link = author.send_announce().await?;
+    println!(
+        "Announcement Link: {}\nTangle Index: {:#}\n",
+        announcement_link, announcement_link.to_msg_index()
+    );
+
+    // ------------------------------------------------------------------
+    // In their own separate instances generate the subscriber(s) that will be attaching to the channel
+    let mut subscriber_a = Subscriber::new("SubscriberA", transport.clone());
+    let mut subscriber_b = Subscriber::new("SubscriberB", transport);
+    subscriber_a.receive_announcement(&announcement_link).await?;
+    subscriber_b.receive_announcement(&announcement_link).await?;
+    let subscribe_msg_a = subscriber_a.send_subscribe(&announcement_link).await?;
+    let subscribe_msg_b = subscriber_b.send_subscribe(&announcement_link).await?;
+    let sub_a_pk = *subscriber_a.get_public_key().as_bytes();
+    let sub_b_pk = *subscriber_b.get_public_key().as_bytes();
+    // ----------------------------------------------------------------------
+
+    author.receive_subscribe(&subscribe_msg_a).await?;
+    author.receive_subscribe(&subscribe_msg_b).await?;
+
+    // Keys are rotated after every 3 messages
+    let mut branch = RotatingBranch::open(
+        &mut author,
+        &announcement_link,
+        Membership::Subscribers(vec![sub_a_pk, sub_b_pk]),
+        RotationPolicy::every_messages(3),
+    ).await?;
+    println!("Opened rotating branch with Keyload: {}", branch.keyload_link());
+
+    let msg_inputs = vec![
+        "These",
+        "Messages",
+        "Are",
+        "Sent",
+        "Under",
+        "Rotating",
+        "Keys",
+        "For",
+        "Both",
+        "Subscribers",
+    ];
+    for input in &msg_inputs {
+        let rotations = branch.rotations();
+        let msg_link = branch.publish(
+            &mut author,
+            &Bytes::default(),
+            &Bytes(input.as_bytes().to_vec()),
+        ).await?;
+        // Publishing rotates the keys first whenever the branch is due
+        if branch.rotations() > rotations {
+            println!("Rotated branch keys ({}), new Keyload: {}", branch.rotations(), branch.keyload_link());
+        }
+        println!("Sent msg: {}, tangle index: {:#}", msg_link, msg_link.to_msg_index());
+    }
+    ensure!(branch.rotations() == 3, "Expected 3 rotations, found {}", branch.rotations());
+
+    // Subscribers pick up each new keyload while reading forward
+    println!("\nVerifying message retrieval: SubscriberA");
+    let retrieved = subscriber_a.fetch_all_next_msgs().await;
+    ensure!(count_keyloads(&retrieved) == 4, "Subscriber A did not follow every rotation");
+    verify_messages(&msg_inputs, retrieved)?;
+    println!("\nVerifying message retrieval: SubscriberB");
+    let retrieved = subscriber_b.fetch_all_next_msgs().await;
+    ensure!(count_keyloads(&retrieved) == 4, "Subscriber B did not follow every rotation");
+    verify_messages(&msg_inputs, retrieved)?;
+
+    // -----------------------------------------------------------------------------
+    // Rotating on age instead, the keys are replaced before the first message sent after they
+    // expire
+    println!("\nRotating keys by age");
+    let mut branch = RotatingBranch::open(
+        &mut author,
+        &announcement_link,
+        Membership::Everyone,
+        RotationPolicy::every_duration(Duration::from_millis(500)),
+    ).await?;
+    println!("Opened rotating branch with Keyload: {}", branch.keyload_link());
+
+    let msg_inputs = vec!["Before", "Expiry", "After", "Expiry"];
+    for (i, input) in msg_inputs.iter().enumerate() {
+        if i == 2 {
+            // Rotation is lazy, so once the keys have expired the branch is rotated from a timer
+            // rather than waiting for the next publish
+            tokio::time::sleep(Duration::from_millis(600)).await;
+            let keyload_link = branch.rotate_if_due(&mut author).await?;
+            ensure!(keyload_link.is_some(), "Expected the expired keys to be rotated");
+            println!("Rotated expired branch keys, new Keyload: {}", branch.keyload_link());
+        }
+        let msg_link = branch.publish(
+            &mut author,
+            &Bytes::default(),
+            &Bytes(input.as_bytes().to_vec()),
+        ).await?;
+        println!("Sent msg: {}, tangle index: {:#}", msg_link, msg_link.to_msg_index());
+    }
+    ensure!(branch.rotations() == 1, "Expected 1 rotation, found {}", branch.rotations());
+
+    println!("\nVerifying message retrieval: SubscriberA");
+    verify_messages(&msg_inputs, subscriber_a.fetch_all_next_msgs().await)?;
+    println!("\nVerifying message retrieval: SubscriberB");
+    verify_messages(&msg_inputs, subscriber_b.fetch_all_next_msgs().await)?;
+
+    Ok(())
+}
+
+fn count_keyloads(msgs: &[UnwrappedMessage]) -> usize {
+    msgs.iter()
+        .filter(|msg| matches!(msg.body, MessageContent::Keyload))
+        .count()
+}
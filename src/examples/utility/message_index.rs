@@ -0,0 +1,126 @@
+use anyhow::ensure;
+use iota_streams::{
+    app_channels::api::tangle::{
+        Author, Bytes, ChannelType, MessageContent, Subscriber, Transport, UnwrappedMessage,
+    },
+    core::{println, Result},
+};
+
+use crate::examples::generate_seed;
+use crate::tools::MessageIndex;
+
+const INDEX_PATH: &str = "./message_index.json";
+const ROUNDS: usize = 3;
+
+/**
+ * This example keeps a local index of a private Single Branch channel in which both the Author and
+ * Subscriber A publish. Subscriber B syncs the channel through a `MessageIndex` stored in
+ * `./message_index.json`, which records the position in the index, link, Tangle index and
+ * publisher of every message it reads. The index is reopened from disk part way through to carry
+ * on syncing, is synced again by a reader starting over without indexing anything twice, and is
+ * then used to jump straight to message N, and to the k-th message of Subscriber A, without
+ * walking the chain again.
+ */
+pub async fn example<T: Transport>(transport: T) -> Result<()> {
+    // Generate a unique seed for the author
+    let seed: &str = &generate_seed();
+
+    // Generate an Author
+    let mut author = Author::new(seed, ChannelType::SingleBranch, transport.clone());
+    let announcement_link = author.send_announce().await?;
+    println!(
+        "Announcement Link: {}\nTangle Index: {:#}\n",
+        announcement_link, announcement_link.to_msg_index()
+    );
+
+    let mut subscriber_a = Subscriber::new("SubscriberA", transport.clone());
+    let mut subscriber_b = Subscriber::new("SubscriberB", transport.clone());
+    for subscriber in [&mut subscriber_a, &mut subscriber_b] {
+        subscriber.receive_announcement(&announcement_link).await?;
+        let subscribe_msg = subscriber.send_subscribe(&announcement_link).await?;
+        author.receive_subscribe(&subscribe_msg).await?;
+    }
+    let (keyload_link, _seq) = author.send_keyload_for_everyone(&announcement_link).await?;
+    let subscriber_a_pk = hex::encode(subscriber_a.get_public_key().as_bytes());
+
+    if std::path::Path::new(INDEX_PATH).exists() {
+        std::fs::remove_file(INDEX_PATH)?;
+    }
+    let mut index = MessageIndex::open(INDEX_PATH)?;
+
+    // The Author and Subscriber A take turns extending the branch
+    let mut prev_msg_link = keyload_link;
+    for round in 0..ROUNDS {
+        let (msg_link, _seq_link) = author.send_signed_packet(
+            &prev_msg_link,
+            &Bytes::default(),
+            &Bytes(format!("Author msg {}", round).into_bytes()),
+        ).await?;
+        prev_msg_link = msg_link;
+
+        subscriber_a.sync_state().await;
+        let (msg_link, _seq_link) = subscriber_a.send_signed_packet(
+            &prev_msg_link,
+            &Bytes::default(),
+            &Bytes(format!("SubscriberA msg {}", round).into_bytes()),
+        ).await?;
+        prev_msg_link = msg_link;
+        author.sync_state().await;
+        println!("Round {}: Author and SubscriberA published", round);
+
+        // Subscriber B indexes the channel after the first round, and then picks the index up
+        // again from disk, as it would after a restart
+        if round == 0 {
+            let msgs = index.sync(&mut subscriber_b).await?;
+            println!("SubscriberB indexed {} msgs", msgs.len());
+            index = MessageIndex::open(INDEX_PATH)?;
+            ensure!(index.len() == msgs.len(), "Reopened index has {} entries", index.len());
+        }
+    }
+
+    // -----------------------------------------------------------------------------
+    let msgs = index.sync(&mut subscriber_b).await?;
+    println!("SubscriberB indexed {} more msgs, {} in total\n", msgs.len(), index.len());
+    // The keyload, followed by one message from each publisher per round
+    ensure!(index.len() == 1 + 2 * ROUNDS, "Index has {} entries", index.len());
+    for position in 0..index.len() {
+        let entry = index.get(position).unwrap();
+        println!(
+            "{}: {} (index {}), publisher {:?}",
+            entry.position, entry.link, entry.msg_index, entry.publisher
+        );
+    }
+
+    // A reader starting over from the announcement retrieves every message again, none of which
+    // are indexed twice
+    let mut restarted_reader = Subscriber::new("SubscriberB", transport);
+    restarted_reader.receive_announcement(&announcement_link).await?;
+    let msgs = index.sync(&mut restarted_reader).await?;
+    println!("\nA restarted SubscriberB read {} msgs again", msgs.len());
+    ensure!(index.len() == 1 + 2 * ROUNDS, "Index has {} entries after re-reading", index.len());
+
+    // Jump straight to the fourth message
+    let msg = index.receive(&mut subscriber_b, 3).await?;
+    let payload = masked_payload(&msg);
+    println!("\nMessage 3: {}", payload);
+    ensure!(payload == "Author msg 1", "Message 3 was {}", payload);
+
+    // And to the last message Subscriber A published
+    let count = index.publisher_msg_count(&subscriber_a_pk);
+    ensure!(count == ROUNDS, "SubscriberA has {} indexed msgs", count);
+    let msg = index.receive_publisher_msg(&mut subscriber_b, &subscriber_a_pk, count - 1).await?;
+    let payload = masked_payload(&msg);
+    println!("SubscriberA message {}: {}", count - 1, payload);
+    ensure!(payload == format!("SubscriberA msg {}", ROUNDS - 1), "Last message was {}", payload);
+
+    Ok(())
+}
+
+fn masked_payload(msg: &UnwrappedMessage) -> String {
+    match &msg.body {
+        MessageContent::SignedPacket { masked_payload, .. } => {
+            String::from_utf8_lossy(&masked_payload.0).to_string()
+        }
+        _ => String::new(),
+    }
+}
@@ -1,6 +1,28 @@
+pub mod backfill;
+pub mod batch_publishing;
+pub mod channel_archive;
+pub mod channel_bridge;
+pub mod channel_visualizer;
+pub mod chunked_payload;
+pub mod compressed_payloads;
+pub mod failure_paths;
 pub mod fetch_prev;
+pub mod file_transfer;
 pub mod grant_and_revoke_access;
+pub mod history_paging;
+pub mod indexed_log;
+pub mod integrity_check;
+pub mod key_rotation;
+pub mod message_index;
+pub mod node_failover;
+pub mod offline_outbox;
+pub mod parallel_catch_up;
 pub mod pk_keyloads;
 pub mod psk_keyloads;
+pub mod scenario_runner;
 pub mod state_recovery;
 pub mod stateless_recovery;
+pub mod tagged_packets;
+#[cfg(test)]
+mod tamper_and_replay;
+pub mod unsubscribe;
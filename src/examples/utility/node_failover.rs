@@ -0,0 +1,124 @@
+use anyhow::ensure;
+use iota_streams::{
+    app_channels::api::tangle::{Author, Bytes, ChannelType, Subscriber},
+    core::{println, Result},
+};
+
+use crate::examples::{expect_failure, generate_seed, verify_messages};
+use crate::transport::{FailoverTransport, MemoryTransport, RetryPolicy, StandInNode};
+use std::time::Duration;
+
+/**
+ * This example runs a channel over a failover transport spread across three local stand-in nodes
+ * that fail on demand, instead of a single node where any error aborts the workflow:
+ * - The preferred node goes down and publishing fails over to the next one
+ * - A node fails requests while still reporting itself healthy, and the requests are retried
+ * - Every node goes down, and publishing gives up once the retries with backoff are exhausted
+ * - The nodes recover and the channel carries on
+ * - A fetch fails once on a healthy node and is retried instead of reporting the message missing
+ *
+ * Throughout, the subscriber reading over its own failover transport sees every message.
+ */
+pub async fn example() -> Result<()> {
+    // Generate a unique seed for the author
+    let seed: &str = &generate_seed();
+
+    // The stand-in nodes share the same messages, like nodes of the same network
+    let storage = MemoryTransport::new();
+    let nodes = vec![
+        StandInNode::new(storage.clone()),
+        StandInNode::new(storage.clone()),
+        StandInNode::new(storage),
+    ];
+    let urls = ["http://node-0.local", "http://node-1.local", "http://node-2.local"];
+    let policy = RetryPolicy {
+        max_attempts: 3,
+        initial_backoff: Duration::from_millis(10),
+        max_backoff: Duration::from_millis(100),
+        multiplier: 2,
+    };
+    let failover = || FailoverTransport::new(
+        urls.iter()
+            .zip(&nodes)
+            .map(|(url, node)| (url.to_string(), node.clone()))
+            .collect(),
+        policy,
+    ).map(|transport| transport.with_listener(|event| println!("{}", event)));
+    let author_transport = failover()?;
+    let subscriber_transport = failover()?;
+
+    // Generate an Author
+    let mut author = Author::new(seed, ChannelType::SingleBranch, author_transport.clone());
+    let announcement_link = author.send_announce().await?;
+    println!(
+        "Announcement Link: {}\nTangle Index: {:#}\n",
+        announcement_link, announcement_link.to_msg_index()
+    );
+
+    let mut subscriber_a = Subscriber::new("SubscriberA", subscriber_transport.clone());
+    subscriber_a.receive_announcement(&announcement_link).await?;
+
+    // -----------------------------------------------------------------------------
+    println!("Taking down {}", urls[0]);
+    nodes[0].go_down();
+    let msg_inputs = vec!["Published", "After", "Failing", "Over"];
+    let mut prev_msg_link = announcement_link;
+    for input in &msg_inputs {
+        let (msg_link, _seq) = author.send_signed_packet(
+            &prev_msg_link,
+            &Bytes::default(),
+            &Bytes(input.as_bytes().to_vec()),
+        ).await?;
+        println!("Sent msg: {}, tangle index: {:#}", msg_link, msg_link.to_msg_index());
+        prev_msg_link = msg_link;
+    }
+    ensure!(
+        author_transport.current_node() == urls[1],
+        "Expected requests to have failed over to {}, using {}", urls[1], author_transport.current_node()
+    );
+
+    println!("\nVerifying message retrieval: SubscriberA");
+    verify_messages(&msg_inputs, subscriber_a.fetch_all_next_msgs().await)?;
+
+    // -----------------------------------------------------------------------------
+    println!("\n{} fails the next 2 requests while reporting itself healthy", urls[1]);
+    nodes[1].fail_next(2);
+    let (msg_link, _seq) = author.send_signed_packet(
+        &prev_msg_link,
+        &Bytes::default(),
+        &Bytes("Retried".as_bytes().to_vec()),
+    ).await?;
+    println!("Sent msg: {}, tangle index: {:#}", msg_link, msg_link.to_msg_index());
+    prev_msg_link = msg_link;
+
+    // -----------------------------------------------------------------------------
+    println!("\nTaking down every node");
+    nodes.iter().for_each(StandInNode::go_down);
+    expect_failure(
+        "Publishing while every node is down",
+        author.send_signed_packet(
+            &prev_msg_link,
+            &Bytes::default(),
+            &Bytes("Never published".as_bytes().to_vec()),
+        ).await,
+    )?;
+
+    println!("\nBringing {} back up", urls[2]);
+    nodes[2].come_up();
+    let (msg_link, _seq) = author.send_signed_packet(
+        &prev_msg_link,
+        &Bytes::default(),
+        &Bytes("Recovered".as_bytes().to_vec()),
+    ).await?;
+    println!("Sent msg: {}, tangle index: {:#}", msg_link, msg_link.to_msg_index());
+
+    // The only node left fails the subscriber's next fetch, which is retried rather than taken as
+    // the message missing
+    println!("\n{} fails the next request while reporting itself healthy", urls[2]);
+    nodes[2].fail_next(1);
+    println!("\nVerifying message retrieval: SubscriberA");
+    verify_messages(&["Retried", "Recovered"], subscriber_a.fetch_all_next_msgs().await)?;
+    println!("Healthy nodes: {:?}", subscriber_transport.healthy_nodes());
+
+    Ok(())
+}
@@ -0,0 +1,101 @@
+use anyhow::{anyhow, ensure};
+use iota_streams::{
+    app_channels::api::tangle::{Author, Bytes, ChannelType, Subscriber},
+    core::{println, Result},
+};
+
+use crate::cli::DEFAULT_PASSWORD;
+use crate::examples::{generate_seed, verify_messages};
+use crate::tools::outbox::{Flushed, Outbox, PacketKind};
+use crate::transport::{MemoryTransport, StandInNode};
+
+const OUTBOX_PATH: &str = "./outbox.json";
+
+/**
+ * This example publishes from a device that loses its connection to the node. Every message goes
+ * through a durable outbox stored in `./outbox.json`, so messages published while the node is
+ * unreachable are queued on disk instead of being lost. The device then restarts, reloading the
+ * outbox along with the Author state persisted in it, and once the node is reachable again the
+ * queued messages are published in order, linked on from the last message that made it through.
+ */
+pub async fn example() -> Result<()> {
+    // Generate a unique seed for the author
+    let seed: &str = &generate_seed();
+
+    // A stand-in node that can be taken down to simulate losing connectivity
+    let node = StandInNode::new(MemoryTransport::new());
+
+    // Generate an Author
+    let mut author = Author::new(seed, ChannelType::SingleBranch, node.clone());
+    let announcement_link = author.send_announce().await?;
+    println!(
+        "Announcement Link: {}\nTangle Index: {:#}\n",
+        announcement_link, announcement_link.to_msg_index()
+    );
+
+    // Start from an empty outbox attached to the announcement
+    if std::path::Path::new(OUTBOX_PATH).exists() {
+        std::fs::remove_file(OUTBOX_PATH)?;
+    }
+    let mut outbox = Outbox::open(OUTBOX_PATH, &announcement_link)?;
+
+    let msg_inputs = vec!["These", "Messages", "Survive", "Losing", "The", "Connection"];
+    for (i, input) in msg_inputs.iter().enumerate() {
+        // The connection drops after the first two messages
+        if i == 2 {
+            println!("\nConnection lost");
+            node.go_down();
+        }
+        let flushed = outbox.publish(
+            &mut author,
+            PacketKind::Signed,
+            &Bytes::default(),
+            &Bytes(input.as_bytes().to_vec()),
+            DEFAULT_PASSWORD,
+        ).await?;
+        print_flushed(&flushed, outbox.pending());
+    }
+    ensure!(outbox.pending() == 4, "Expected 4 queued messages, found {}", outbox.pending());
+
+    // -----------------------------------------------------------------------------
+    // The device restarts, reloading the outbox and the Author state persisted with it
+    drop(author);
+    drop(outbox);
+    println!("\nDevice restarted with the connection still down");
+
+    let mut outbox = Outbox::open(OUTBOX_PATH, &announcement_link)?;
+    let state = outbox
+        .writer_state()?
+        .ok_or_else(|| anyhow!("No Author state was persisted with the outbox"))?;
+    let mut author = Author::import(&state, DEFAULT_PASSWORD, node.clone()).await?;
+    println!(
+        "Reloaded outbox with {} queued msgs, linking on from {}",
+        outbox.pending(), outbox.prev_link()?
+    );
+
+    println!("\nConnection restored");
+    node.come_up();
+    let flushed = outbox.flush(&mut author, DEFAULT_PASSWORD).await?;
+    print_flushed(&flushed, outbox.pending());
+    ensure!(
+        flushed.sent.len() == 4 && outbox.pending() == 0,
+        "Outbox was not emptied after reconnecting"
+    );
+
+    // -----------------------------------------------------------------------------
+    let mut subscriber_a = Subscriber::new("SubscriberA", node);
+    subscriber_a.receive_announcement(&announcement_link).await?;
+    println!("\nVerifying message retrieval: SubscriberA");
+    verify_messages(&msg_inputs, subscriber_a.fetch_all_next_msgs().await)?;
+
+    Ok(())
+}
+
+fn print_flushed(flushed: &Flushed, pending: usize) {
+    for msg_link in &flushed.sent {
+        println!("Sent queued msg: {}, tangle index: {:#}", msg_link, msg_link.to_msg_index());
+    }
+    if let Some(e) = &flushed.stopped_by {
+        println!("Publishing stopped with {} msgs queued: {}", pending, e);
+    }
+}
@@ -0,0 +1,138 @@
+use anyhow::ensure;
+use iota_streams::{
+    app_channels::api::tangle::{
+        Author, Bytes, ChannelType, MessageContent, PublicKey, Subscriber, UnwrappedMessage,
+    },
+    core::{println, Result},
+};
+
+use crate::cli::DEFAULT_PASSWORD;
+use crate::examples::generate_seed;
+use crate::tools::catch_up;
+use crate::transport::{MemoryTransport, PrefetchTransport, StandInNode};
+use std::time::{Duration, Instant};
+
+const PUBLISHERS: usize = 6;
+const MSGS_PER_PUBLISHER: usize = 4;
+const CONCURRENCY: usize = 4;
+// Round trip added to every request to the stand-in node
+const LATENCY: Duration = Duration::from_millis(10);
+
+/**
+ * This example catches up a fresh reader on a channel where many subscribers publish in their own
+ * branches, as in the single publisher per branch example. An Auditor included in every branch
+ * reads the whole channel twice from the same starting state, over a stand-in node with a fixed
+ * latency: once with a sequential `fetch_all_next_msgs` sweep, and once with `catch_up`, which
+ * prefetches the next link of every publisher concurrently. Both read the same messages, and the
+ * order returned by `catch_up` is deterministic.
+ */
+pub async fn example() -> Result<()> {
+    // Generate a unique seed for the author
+    let seed: &str = &generate_seed();
+
+    let node = StandInNode::new(MemoryTransport::new());
+
+    // Generate an Author
+    let mut author = Author::new(seed, ChannelType::MultiBranch, node.clone());
+    let announcement_link = author.send_announce().await?;
+    println!(
+        "Announcement Link: {}\nTangle Index: {:#}\n",
+        announcement_link, announcement_link.to_msg_index()
+    );
+
+    // The Auditor is given access to every branch
+    let mut auditor = Subscriber::new("Auditor", node.clone());
+    auditor.receive_announcement(&announcement_link).await?;
+    let subscribe_msg = auditor.send_subscribe(&announcement_link).await?;
+    author.receive_subscribe(&subscribe_msg).await?;
+    let auditor_pk = *auditor.get_public_key().as_bytes();
+    let auditor_state = auditor.export(DEFAULT_PASSWORD).await?;
+
+    // Each publisher is given its own branch and publishes a chain of messages in it
+    for i in 0..PUBLISHERS {
+        let mut publisher = Subscriber::new(&format!("Publisher{}", i), node.clone());
+        publisher.receive_announcement(&announcement_link).await?;
+        let subscribe_msg = publisher.send_subscribe(&announcement_link).await?;
+        author.receive_subscribe(&subscribe_msg).await?;
+
+        let (keyload_link, _seq) = author.send_keyload(
+            &announcement_link,
+            &vec![
+                PublicKey::from_bytes(publisher.get_public_key().as_bytes())?.into(),
+                PublicKey::from_bytes(&auditor_pk)?.into(),
+            ],
+        ).await?;
+        publisher.sync_state().await;
+
+        let mut prev_msg_link = keyload_link;
+        for j in 0..MSGS_PER_PUBLISHER {
+            let (msg_link, _seq) = publisher.send_signed_packet(
+                &prev_msg_link,
+                &Bytes::default(),
+                &Bytes(format!("Publisher{} msg {}", i, j).into_bytes()),
+            ).await?;
+            prev_msg_link = msg_link;
+        }
+        println!("Publisher{} sent {} msgs in its own branch", i, MSGS_PER_PUBLISHER);
+    }
+
+    // -----------------------------------------------------------------------------
+    node.set_latency(LATENCY);
+
+    println!("\nCatching up sequentially");
+    let mut sequential_reader =
+        Subscriber::import(&auditor_state, DEFAULT_PASSWORD, node.clone()).await?;
+    let start = Instant::now();
+    let sequential = sequential_reader.fetch_all_next_msgs().await;
+    let sequential_time = start.elapsed();
+    println!("Read {} msgs in {:?}", sequential.len(), sequential_time);
+
+    println!("\nCatching up with {} requests in flight", CONCURRENCY);
+    let transport = PrefetchTransport::new(node.clone());
+    let mut parallel_reader =
+        Subscriber::import(&auditor_state, DEFAULT_PASSWORD, transport.clone()).await?;
+    let start = Instant::now();
+    let caught_up = catch_up(&mut parallel_reader, &transport, CONCURRENCY).await;
+    let parallel_time = start.elapsed();
+    println!("Read {} msgs in {:?}", caught_up.msgs.len(), parallel_time);
+    for (link, e) in &caught_up.failed {
+        println!("Prefetching {} failed: {}", link, e);
+    }
+    ensure!(caught_up.failed.is_empty(), "{} links failed to prefetch", caught_up.failed.len());
+    let parallel = caught_up.msgs;
+
+    // -----------------------------------------------------------------------------
+    let mut expected = (0..PUBLISHERS)
+        .flat_map(|i| (0..MSGS_PER_PUBLISHER).map(move |j| format!("Publisher{} msg {}", i, j)))
+        .collect::<Vec<String>>();
+    expected.sort();
+    let mut sequential_payloads = masked_payloads(&sequential);
+    sequential_payloads.sort();
+    ensure!(sequential_payloads == expected, "Sequential sweep read {:?}", sequential_payloads);
+    let parallel_payloads = masked_payloads(&parallel);
+    let mut sorted = parallel_payloads.clone();
+    sorted.sort();
+    ensure!(sorted == expected, "Catch up read {:?}", parallel_payloads);
+
+    // A second catch up from the same state merges the messages in exactly the same order
+    let transport = PrefetchTransport::new(node);
+    let mut second_reader =
+        Subscriber::import(&auditor_state, DEFAULT_PASSWORD, transport.clone()).await?;
+    let second = masked_payloads(&catch_up(&mut second_reader, &transport, CONCURRENCY).await.msgs);
+    ensure!(second == parallel_payloads, "Catch up order was not deterministic");
+    println!("\nBoth readers read all {} msgs, catch up order is deterministic", expected.len());
+    println!("Sequential: {:?}, catch up: {:?}", sequential_time, parallel_time);
+
+    Ok(())
+}
+
+fn masked_payloads(msgs: &[UnwrappedMessage]) -> Vec<String> {
+    msgs.iter()
+        .filter_map(|msg| match &msg.body {
+            MessageContent::SignedPacket { masked_payload, .. } => {
+                Some(String::from_utf8_lossy(&masked_payload.0).to_string())
+            }
+            _ => None,
+        })
+        .collect()
+}
@@ -1,14 +1,13 @@
 use iota_streams::{
-    app::transport::tangle::client::Client,
     app_channels::api::{
         psk_from_seed,
         pskid_from_psk,
-        tangle::{Address, Author, ChannelType, Subscriber}
+        tangle::{Address, Author, ChannelType, Subscriber, Transport}
     },
     core::{println, Result},
 };
 
-use crate::examples::ALPH9;
+use crate::examples::generate_seed;
 use rand::Rng;
 use core::str::FromStr;
 
@@ -16,22 +15,12 @@ use core::str::FromStr;
  * This example demonstrates how to use a Pre-Shared Key for accessing a branch. PSK's should only
  * be used for read access at this time.
  */
-pub async fn example(node_url: &str) -> Result<()> {
+pub async fn example<T: Transport>(transport: T) -> Result<()> {
     // Generate a unique seed for the author
-    let seed: &str = &(0..81)
-        .map(|_| {
-            ALPH9
-                .chars()
-                .nth(rand::thread_rng().gen_range(0, 27))
-                .unwrap()
-        })
-        .collect::<String>();
-
-    // Create the Transport Client
-    let client = Client::new_from_url(node_url);
+    let seed: &str = &generate_seed();
 
     // Generate an Author
-    let mut author = Author::new(seed, ChannelType::MultiBranch, client.clone());
+    let mut author = Author::new(seed, ChannelType::MultiBranch, transport.clone());
 
     // Create the channel with an announcement message. Make sure to save the resulting link somewhere,
     let announcement_link = author.send_announce().await?;
@@ -53,7 +42,7 @@ pub async fn example(node_url: &str) -> Result<()> {
 
     // ------------------------------------------------------------------
     // In their own separate instances generate the subscriber(s) that will be attaching to the channel
-    let mut subscriber = Subscriber::new("SubscriberA", client);
+    let mut subscriber = Subscriber::new("SubscriberA", transport);
 
     // Generate an Address object from the provided announcement link string from the Author
     let ann_address = Address::from_str(&ann_link_string)?;
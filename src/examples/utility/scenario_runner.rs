@@ -0,0 +1,38 @@
+use iota_streams::{
+    app_channels::api::tangle::Transport,
+    core::{println, Result},
+};
+
+use crate::tools::Scenario;
+
+/**
+ * This example runs every scenario file in the `scenarios` directory. Each file describes one of
+ * the channel topologies from the other examples as data, and the runner checks that every
+ * identity reads exactly the messages it is expected to.
+ */
+pub async fn example<T: Transport>(transport: T) -> Result<()> {
+    let dir = concat!(env!("CARGO_MANIFEST_DIR"), "/scenarios");
+
+    let mut paths = std::fs::read_dir(dir)?
+        .map(|entry| entry.map(|entry| entry.path()))
+        .collect::<std::io::Result<Vec<_>>>()?;
+    paths.sort();
+
+    for path in paths.iter().filter(|path| path.extension().map_or(false, |ext| ext == "yaml")) {
+        let scenario = Scenario::from_yaml(&std::fs::read_to_string(path)?)?;
+        println!("\nRunning scenario: {}", scenario.name);
+        let report = scenario.run(transport.clone()).await?;
+        println!("Announcement Link: {}", report.announcement_link);
+        for (branch, keyload_link) in &report.keyloads {
+            println!("Sent Keyload for branch {}: {}", branch, keyload_link);
+        }
+        for (branch, publisher, msg_link) in &report.messages {
+            println!("Sent msg in branch {} from {}: {}", branch, publisher, msg_link);
+        }
+        for (name, read) in &report.read {
+            println!("{} read {} messages as expected", name, read);
+        }
+    }
+
+    Ok(())
+}
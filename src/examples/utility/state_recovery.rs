@@ -1,28 +1,16 @@
 use iota_streams::{
-    app::transport::tangle::client::Client,
-    app_channels::api::tangle::{Author, Bytes, ChannelType},
+    app_channels::api::tangle::{Author, Bytes, ChannelType, Transport},
     core::{println, Result},
 };
 
-use crate::examples::{verify_messages, ALPH9};
-use rand::Rng;
+use crate::examples::{generate_seed, verify_messages};
 
-pub async fn example(node_url: &str) -> Result<()> {
+pub async fn example<T: Transport>(transport: T) -> Result<()> {
     // Generate a unique seed for the author
-    let seed: &str = &(0..81)
-        .map(|_| {
-            ALPH9
-                .chars()
-                .nth(rand::thread_rng().gen_range(0, 27))
-                .unwrap()
-        })
-        .collect::<String>();
-
-    // Create the Transport Client
-    let client = Client::new_from_url(node_url);
+    let seed: &str = &generate_seed();
 
     // Generate an Author
-    let mut author = Author::new(seed, ChannelType::SingleBranch, client.clone());
+    let mut author = Author::new(seed, ChannelType::SingleBranch, transport.clone());
 
     // Create the channel with an announcement message. Make sure to save the resulting link somewhere,
     let announcement_link = author.send_announce().await?;
@@ -54,7 +42,7 @@ pub async fn example(node_url: &str) -> Result<()> {
     let state = std::fs::read("./author_state.bin")?;
 
     // Import state
-    let mut new_author = Author::import(&state, "Password", client).await?;
+    let mut new_author = Author::import(&state, "Password", transport).await?;
 
     let (last_msg_link, _seq) = new_author.send_signed_packet(
         &prev_msg_link,
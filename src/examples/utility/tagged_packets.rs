@@ -0,0 +1,109 @@
+use iota_streams::{
+    app_channels::api::tangle::{Address, Author, ChannelType, PublicKey, Subscriber, Transport},
+    core::{println, Result},
+};
+
+use crate::examples::{generate_seed, verify_messages};
+use crate::tools::send_tagged_packets;
+
+/**
+ * This example publishes tagged packets instead of signed packets. Tagged packets are not signed
+ * by their publisher, which makes them cheaper to send and to read, and suits high frequency data
+ * such as sensor readings sent by subscribers. Their masked payloads are still only readable by the
+ * users included in the keyload of the branch, but readers can't tell which publisher sent them.
+ *
+ * In a Single Branch channel the Author and a Subscriber take turns publishing tagged packets in
+ * the same chain. In a Multi Branch channel two Subscribers publish their own chains of readings
+ * off the same keyload, which the Author then reads.
+ */
+pub async fn example<T: Transport>(transport: T) -> Result<()> {
+    // Generate a unique seed for the author
+    let seed: &str = &generate_seed();
+
+    // -----------------------------------------------------------------------------
+    println!("Tagged packets in a Single Branch channel");
+    let mut author = Author::new(seed, ChannelType::SingleBranch, transport.clone());
+    let announcement_link = author.send_announce().await?;
+    println!(
+        "Announcement Link: {}\nTangle Index: {:#}\n",
+        announcement_link, announcement_link.to_msg_index()
+    );
+
+    let mut subscriber_a = Subscriber::new("SubscriberA", transport.clone());
+    subscriber_a.receive_announcement(&announcement_link).await?;
+    let subscribe_msg_a = subscriber_a.send_subscribe(&announcement_link).await?;
+    author.receive_subscribe(&subscribe_msg_a).await?;
+
+    let (keyload_link, _seq) = author.send_keyload_for_everyone(&announcement_link).await?;
+    println!("Sent Keyload for everyone: {}", keyload_link);
+
+    // The Author publishes tagged packets attached to the keyload
+    let author_inputs = vec!["These", "Tagged", "Messages", "Are", "From", "The", "Author"];
+    let (author_links, _seq) = send_tagged_packets(&mut author, &keyload_link, &author_inputs).await?;
+    print_sent(&author_links);
+
+    println!("\nVerifying message retrieval: SubscriberA");
+    verify_messages(&author_inputs, subscriber_a.fetch_all_next_msgs().await)?;
+
+    // Subscriber A is now synced to the end of the branch and continues the chain with its own
+    // readings
+    let readings_a = vec!["21.4", "21.6", "21.9", "22.3"];
+    let (links, _seq) = send_tagged_packets(&mut subscriber_a, author_links.last().unwrap(), &readings_a).await?;
+    print_sent(&links);
+
+    println!("\nVerifying message retrieval: Author");
+    verify_messages(&readings_a, author.fetch_all_next_msgs().await)?;
+
+    // -----------------------------------------------------------------------------
+    println!("\nTagged packets in a Multi Branch channel");
+    let seed: &str = &generate_seed();
+    let mut author = Author::new(seed, ChannelType::MultiBranch, transport.clone());
+    let announcement_link = author.send_announce().await?;
+    println!(
+        "Announcement Link: {}\nTangle Index: {:#}\n",
+        announcement_link, announcement_link.to_msg_index()
+    );
+
+    let mut subscriber_a = Subscriber::new("SubscriberA", transport.clone());
+    let mut subscriber_b = Subscriber::new("SubscriberB", transport);
+    subscriber_a.receive_announcement(&announcement_link).await?;
+    subscriber_b.receive_announcement(&announcement_link).await?;
+    let subscribe_msg_a = subscriber_a.send_subscribe(&announcement_link).await?;
+    let subscribe_msg_b = subscriber_b.send_subscribe(&announcement_link).await?;
+    author.receive_subscribe(&subscribe_msg_a).await?;
+    author.receive_subscribe(&subscribe_msg_b).await?;
+    let sub_a_pk = subscriber_a.get_public_key().as_bytes();
+    let sub_b_pk = subscriber_b.get_public_key().as_bytes();
+
+    // Both subscribers are given write access to a branch for their readings
+    let (keyload_link, _seq) = author.send_keyload(
+        &announcement_link,
+        &vec![PublicKey::from_bytes(sub_a_pk)?.into(), PublicKey::from_bytes(sub_b_pk)?.into()],
+    ).await?;
+    println!("Sent Keyload for Sub A and B: {}", keyload_link);
+
+    // Each subscriber publishes its own chain of readings attached to the keyload
+    subscriber_a.sync_state().await;
+    let readings_a = vec!["A:1013", "A:1012", "A:1012", "A:1011"];
+    let (links, _seq) = send_tagged_packets(&mut subscriber_a, &keyload_link, &readings_a).await?;
+    print_sent(&links);
+
+    println!("\nVerifying message retrieval from Sub A: Author");
+    verify_messages(&readings_a, author.fetch_all_next_msgs().await)?;
+
+    subscriber_b.sync_state().await;
+    let readings_b = vec!["B:998", "B:999", "B:1001"];
+    let (links, _seq) = send_tagged_packets(&mut subscriber_b, &keyload_link, &readings_b).await?;
+    print_sent(&links);
+
+    println!("\nVerifying message retrieval from Sub B: Author");
+    verify_messages(&readings_b, author.fetch_all_next_msgs().await)?;
+
+    Ok(())
+}
+
+fn print_sent(links: &[Address]) {
+    for link in links {
+        println!("Sent tagged msg: {}, tangle index: {:#}", link, link.to_msg_index());
+    }
+}
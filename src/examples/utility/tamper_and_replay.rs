@@ -0,0 +1,159 @@
+/*!
+ * Tests that modified and replayed messages are rejected. Each test publishes the channel of the
+ * Private Single Branch example over an offline `MemoryTransport`, modifies the stored messages
+ * directly, and then reads the channel as a freshly started Subscriber A of that example:
+ * - Flipping a byte inside a signed packet
+ * - Replaying an old packet at the address the next message is expected at
+ * - Swapping the signatures of two packets
+ *
+ * In each case the subscriber must stop reading at the modified message, and receiving it directly
+ * must fail, with the error reported.
+ */
+use anyhow::{ensure, Result};
+use futures::executor::block_on;
+use iota_streams::app_channels::api::tangle::{
+    Address, MessageContent, Subscriber, UnwrappedMessage,
+};
+
+use crate::examples::{expect_failure, single_branch_private};
+use crate::tools::ChannelReader;
+use crate::transport::MemoryTransport;
+
+// Length of the ed25519 signature closing a signed packet
+const SIGNATURE_LEN: usize = 64;
+// The payloads published by the Private Single Branch example, in order
+const MSG_INPUTS: [&str; 10] = [
+    "These", "Messages", "Will", "Be", "Masked", "And", "Sent", "In", "A", "Chain",
+];
+
+#[test]
+fn flipped_byte_is_rejected() {
+    block_on(flipped_byte()).unwrap();
+}
+
+#[test]
+fn replayed_message_is_rejected() {
+    block_on(replayed_message()).unwrap();
+}
+
+#[test]
+fn swapped_signatures_are_rejected() {
+    block_on(swapped_signatures()).unwrap();
+}
+
+async fn flipped_byte() -> Result<()> {
+    let (transport, announcement_link, msg_links) = published_channel().await?;
+    transport.tamper(&msg_links[2], |msg| {
+        let mid = msg.binary.body.bytes.len() / 2;
+        msg.binary.body.bytes[mid] ^= 0x01;
+    });
+
+    let mut reader = fresh_reader(&transport, &announcement_link).await?;
+    let payloads = masked_payloads(&reader.fetch_all_next_msgs().await);
+    ensure!(payloads == MSG_INPUTS[..2], "Tampered message was accepted, read {:?}", payloads);
+    expect_failure(
+        "Receiving the tampered message directly",
+        reader.receive_signed_packet(&msg_links[2]).await,
+    )?;
+    Ok(())
+}
+
+async fn replayed_message() -> Result<()> {
+    let (transport, announcement_link, msg_links) = published_channel().await?;
+    let mut reader = fresh_reader(&transport, &announcement_link).await?;
+    reader.fetch_all_next_msgs().await;
+
+    // The first packet is stored again at every address the reader expects a message at next
+    let next_links = reader.next_msg_links();
+    for next_link in &next_links {
+        for mut msg in transport.messages_at(&msg_links[0]) {
+            msg.binary.link = next_link.clone();
+            transport.insert(msg);
+        }
+    }
+
+    let payloads = masked_payloads(&reader.fetch_all_next_msgs().await);
+    ensure!(payloads.is_empty(), "Replayed message was accepted, read {:?}", payloads);
+    for next_link in &next_links {
+        expect_failure(
+            "Receiving the replayed message directly",
+            reader.receive_signed_packet(next_link).await,
+        )?;
+    }
+    Ok(())
+}
+
+async fn swapped_signatures() -> Result<()> {
+    let (transport, announcement_link, msg_links) = published_channel().await?;
+    let sig_4 = signature(&transport, &msg_links[3]);
+    let sig_5 = signature(&transport, &msg_links[4]);
+    set_signature(&transport, &msg_links[3], &sig_5);
+    set_signature(&transport, &msg_links[4], &sig_4);
+
+    let mut reader = fresh_reader(&transport, &announcement_link).await?;
+    let payloads = masked_payloads(&reader.fetch_all_next_msgs().await);
+    ensure!(
+        payloads == MSG_INPUTS[..3],
+        "Message with a swapped signature was accepted, read {:?}", payloads
+    );
+    expect_failure(
+        "Receiving message 4 with the signature of message 5 directly",
+        reader.receive_signed_packet(&msg_links[3]).await,
+    )?;
+    expect_failure(
+        "Receiving message 5 with the signature of message 4 directly",
+        reader.receive_signed_packet(&msg_links[4]).await,
+    )?;
+    Ok(())
+}
+
+// Run the Private Single Branch example, returning its transport, announcement link and the links
+// of its packets as read back by Subscriber A before anything is modified
+async fn published_channel() -> Result<(MemoryTransport, Address, Vec<Address>)> {
+    let transport = MemoryTransport::new();
+    let announcement_link = single_branch_private::example(transport.clone()).await?;
+
+    let mut reader = fresh_reader(&transport, &announcement_link).await?;
+    let msgs = reader.fetch_all_next_msgs().await;
+    ensure!(masked_payloads(&msgs) == MSG_INPUTS, "The example channel could not be read back");
+    let msg_links = msgs
+        .iter()
+        .filter(|msg| matches!(msg.body, MessageContent::SignedPacket { .. }))
+        .map(|msg| msg.link.clone())
+        .collect();
+    Ok((transport, announcement_link, msg_links))
+}
+
+// A new instance of the example's Subscriber A that has only seen the announcement
+async fn fresh_reader(
+    transport: &MemoryTransport,
+    announcement_link: &Address,
+) -> Result<Subscriber<MemoryTransport>> {
+    let mut reader = Subscriber::new("SubscriberA", transport.clone());
+    reader.receive_announcement(announcement_link).await?;
+    Ok(reader)
+}
+
+fn masked_payloads(msgs: &[UnwrappedMessage]) -> Vec<String> {
+    msgs.iter()
+        .filter_map(|msg| match &msg.body {
+            MessageContent::SignedPacket { masked_payload, .. } => {
+                Some(String::from_utf8_lossy(&masked_payload.0).to_string())
+            }
+            _ => None,
+        })
+        .collect()
+}
+
+fn signature(transport: &MemoryTransport, link: &Address) -> Vec<u8> {
+    let msgs = transport.messages_at(link);
+    let bytes = &msgs[0].binary.body.bytes;
+    bytes[bytes.len() - SIGNATURE_LEN..].to_vec()
+}
+
+fn set_signature(transport: &MemoryTransport, link: &Address, signature: &[u8]) {
+    transport.tamper(link, |msg| {
+        let len = msg.binary.body.bytes.len();
+        msg.binary.body.bytes[len - SIGNATURE_LEN..].copy_from_slice(signature);
+    });
+}
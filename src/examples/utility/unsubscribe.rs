@@ -0,0 +1,112 @@
+use anyhow::ensure;
+use iota_streams::{
+    app_channels::api::tangle::{
+        Address, Author, Bytes, ChannelType, MessageContent, Subscriber, Transport,
+        UnwrappedMessage,
+    },
+    core::{println, Result},
+};
+
+use crate::examples::{generate_seed, verify_messages};
+
+/**
+ * This example shows a subscriber leaving a channel. Subscriber A sends an unsubscribe message
+ * linked to its original subscription, and once the Author processes it the subscriber's public
+ * key is removed from the Author's subscriber set. Keyloads sent with `send_keyload_for_everyone`
+ * from then on no longer include Subscriber A, so it can't read any messages published afterwards
+ * while Subscriber B keeps its access.
+ */
+pub async fn example<T: Transport>(transport: T) -> Result<()> {
+    // Generate a unique seed for the author
+    let seed: &str = &generate_seed();
+
+    // Generate an Author
+    let mut author = Author::new(seed, ChannelType::MultiBranch, transport.clone());
+    let announcement_link = author.send_announce().await?;
+    println!(
+        "Announcement Link: {}\nTangle Index: {:#}\n",
+        announcement_link, announcement_link.to_msg_index()
+    );
+
+    // ------------------------------------------------------------------
+    // In their own separate instances generate the subscriber(s) that will be attaching to the channel
+    let mut subscriber_a = Subscriber::new("SubscriberA", transport.clone());
+    let mut subscriber_b = Subscriber::new("SubscriberB", transport);
+    subscriber_a.receive_announcement(&announcement_link).await?;
+    subscriber_b.receive_announcement(&announcement_link).await?;
+    let subscribe_msg_a = subscriber_a.send_subscribe(&announcement_link).await?;
+    let subscribe_msg_b = subscriber_b.send_subscribe(&announcement_link).await?;
+    // ----------------------------------------------------------------------
+
+    author.receive_subscribe(&subscribe_msg_a).await?;
+    author.receive_subscribe(&subscribe_msg_b).await?;
+
+    // Both subscribers are included in the first keyload
+    let (keyload_link, seq_link) = author.send_keyload_for_everyone(&announcement_link).await?;
+    println!("Sent Keyload for everyone: {}", keyload_link);
+    let msg_inputs_1 = vec!["These", "Messages", "Are", "Readable", "By", "Both"];
+    let seq_link = send_msgs(&mut author, keyload_link, seq_link.unwrap(), &msg_inputs_1).await?;
+
+    println!("\nVerifying message retrieval before unsubscribing: SubscriberA");
+    verify_messages(&msg_inputs_1, subscriber_a.fetch_all_next_msgs().await)?;
+    println!("\nVerifying message retrieval before unsubscribing: SubscriberB");
+    verify_messages(&msg_inputs_1, subscriber_b.fetch_all_next_msgs().await)?;
+
+    // -----------------------------------------------------------------------------
+    // Subscriber A leaves the channel by sending an unsubscribe message linked to its subscription
+    // message. This link needs to be provided to the Author in the same way as the subscription.
+    let unsubscribe_msg_a = subscriber_a.send_unsubscribe(&subscribe_msg_a).await?;
+    println!(
+        "\nUnsubscribe msg:\n\tSubscriber A: {}\n\tTangle Index: {:#}",
+        unsubscribe_msg_a, unsubscribe_msg_a.to_msg_index()
+    );
+
+    // Processing the unsubscribe removes Subscriber A's public key from the Author's subscriber set
+    author.receive_unsubscribe(&unsubscribe_msg_a).await?;
+
+    // The next keyload for everyone is attached to the sequence message of the previous branch so
+    // that both subscribers find it, but it only includes Subscriber B
+    let (keyload_link, seq_link) = author.send_keyload_for_everyone(&seq_link).await?;
+    println!("\nSent Keyload for everyone after Sub A unsubscribed: {}", keyload_link);
+    let msg_inputs_2 = vec!["These", "Messages", "Are", "Only", "Readable", "By", "B"];
+    send_msgs(&mut author, keyload_link, seq_link.unwrap(), &msg_inputs_2).await?;
+
+    println!("\nVerifying message retrieval after unsubscribing: SubscriberA");
+    let retrieved_a = masked_packets(subscriber_a.fetch_all_next_msgs().await);
+    ensure!(
+        retrieved_a.is_empty(),
+        "Unsubscribed Subscriber A read {} messages published after it left", retrieved_a.len()
+    );
+    verify_messages(&[], retrieved_a)?;
+    println!("\nVerifying message retrieval after unsubscribing: SubscriberB");
+    verify_messages(&msg_inputs_2, subscriber_b.fetch_all_next_msgs().await)?;
+
+    Ok(())
+}
+
+// Send a chain of signed packets, returning the last sequence message link
+async fn send_msgs<T: Transport>(
+    author: &mut Author<T>,
+    mut prev_msg_link: Address,
+    mut seq_msg_link: Address,
+    msg_inputs: &[&str],
+) -> Result<Address> {
+    for input in msg_inputs {
+        let (msg_link, seq_link) = author.send_signed_packet(
+            &prev_msg_link,
+            &Bytes::default(),
+            &Bytes(input.as_bytes().to_vec()),
+        ).await?;
+        println!("Sent msg: {}, tangle index: {:#}", msg_link, msg_link.to_msg_index());
+        prev_msg_link = msg_link;
+        seq_msg_link = seq_link.unwrap();
+    }
+    Ok(seq_msg_link)
+}
+
+// Keep only the signed packets, the keyload excluding a subscriber is still retrieved by it
+fn masked_packets(msgs: Vec<UnwrappedMessage>) -> Vec<UnwrappedMessage> {
+    msgs.into_iter()
+        .filter(|msg| matches!(msg.body, MessageContent::SignedPacket { .. }))
+        .collect()
+}
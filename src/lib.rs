@@ -1 +1,4 @@
+pub mod cli;
 pub mod examples;
+pub mod tools;
+pub mod transport;
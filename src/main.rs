@@ -1,72 +1,188 @@
 use anyhow::Result;
-
-mod examples;
+use iota_streams::{
+    app::transport::tangle::client::Client,
+    app_channels::api::tangle::Transport,
+};
+use streams_examples::{
+    examples,
+    transport::{FailoverTransport, FileTransport, RetryPolicy, SqliteTransport},
+};
 
 #[tokio::main]
 async fn main() -> Result<()> {
-    let url = "https://chrysalis-nodes.iota.org";
+    let args: Vec<String> = std::env::args().collect();
+
+    match args.get(1).map(String::as_str) {
+        // Run the examples against a shared local directory instead of a node:
+        //   cargo run --release -- fs ./channel_data
+        Some("fs") => {
+            let dir = args.get(2).map(String::as_str).unwrap_or("./channel_data");
+            run_examples(FileTransport::new(dir)?).await
+        }
+        // Run the examples against a local SQLite archive:
+        //   cargo run --release -- sqlite ./channel.db
+        Some("sqlite") => {
+            let path = args.get(2).map(String::as_str).unwrap_or("./channel.db");
+            run_examples(SqliteTransport::new(path)?).await
+        }
+        // Run the examples against several nodes, failing over between them:
+        //   cargo run --release -- nodes https://node-a https://node-b
+        Some("nodes") if args.len() > 2 => {
+            let urls: Vec<&str> = args[2..].iter().map(String::as_str).collect();
+            let transport = FailoverTransport::from_urls(&urls, RetryPolicy::default())?
+                .with_listener(|event| println!("{}", event));
+            run_examples(transport).await
+        }
+        _ => {
+            let url = "https://chrysalis-nodes.iota.org";
+            run_examples(Client::new_from_url(url)).await
+        }
+    }
+}
 
+async fn run_examples<T: Transport>(transport: T) -> Result<()> {
     println!("Starting Examples");
     println!("---------------------------------------");
     println!("Single Publisher Examples");
 
     println!("\n---------------------------------------");
     println!("\nPublic - Single Branch - Single Publisher\n");
-    examples::single_branch_public::example(url).await?;
+    examples::single_branch_public::example(transport.clone()).await?;
 
     println!("\n---------------------------------------");
     println!("\nPrivate - Single Branch - Single Publisher\n");
-    examples::single_branch_private::example(url).await?;
+    examples::single_branch_private::example(transport.clone()).await?;
 
     println!("\n---------------------------------------");
     println!("\nPublic - Single Depth - Single Publisher\n");
-    examples::single_depth_public::example(url).await?;
+    examples::single_depth_public::example(transport.clone()).await?;
 
     println!("\n---------------------------------------");
     println!("\nPrivate - Single Depth - Single Publisher\n");
-    examples::single_depth_private::example(url).await?;
+    examples::single_depth_private::example(transport.clone()).await?;
 
     println!("\n---------------------------------------");
     println!("\nMixed - Multi Branch - Single Publisher\n");
-    examples::multi_branch_mixed_privacy::example(url).await?;
+    examples::multi_branch_mixed_privacy::example(transport.clone()).await?;
 
     println!("\n---------------------------------------");
     println!("Multiple Publisher Examples");
 
     println!("\n---------------------------------------");
     println!("\nPrivate - Multi Branch - Single Publisher per Branch\n");
-    examples::single_pub_per_branch::example(url).await?;
+    examples::single_pub_per_branch::example(transport.clone()).await?;
 
     println!("\n---------------------------------------");
     println!("\nPrivate - Multi Branch - Multiple Publishers per Branch\n");
-    examples::multi_pub_per_branch::example(url).await?;
+    examples::multi_pub_per_branch::example(transport.clone()).await?;
 
     println!("\n---------------------------------------");
     println!("Utility Examples");
 
     println!("\n---------------------------------------");
     println!("\nPrevious Message Retrieval\n");
-    examples::fetch_prev::example(url).await?;
+    examples::fetch_prev::example(transport.clone()).await?;
+
+    println!("\n---------------------------------------");
+    println!("\nPaging Through Message History\n");
+    examples::history_paging::example(transport.clone()).await?;
+
+    println!("\n---------------------------------------");
+    println!("\nIndexing a Single Branch Channel\n");
+    examples::message_index::example(transport.clone()).await?;
+
+    println!("\n---------------------------------------");
+    println!("\nChunking Large Payloads\n");
+    examples::chunked_payload::example(transport.clone()).await?;
+
+    println!("\n---------------------------------------");
+    println!("\nCompressing Payloads\n");
+    examples::compressed_payloads::example(transport.clone()).await?;
 
     println!("\n---------------------------------------");
     println!("\nGranting and Revoking Access\n");
-    examples::grant_and_revoke_access::example(url).await?;
+    examples::grant_and_revoke_access::example(transport.clone()).await?;
+
+    println!("\n---------------------------------------");
+    println!("\nBackfilling History for Late Joiners\n");
+    examples::backfill::example(transport.clone()).await?;
+
+    println!("\n---------------------------------------");
+    println!("\nUnsubscribing From a Channel\n");
+    examples::unsubscribe::example(transport.clone()).await?;
+
+    println!("\n---------------------------------------");
+    println!("\nPublishing Tagged Packets\n");
+    examples::tagged_packets::example(transport.clone()).await?;
+
+    println!("\n---------------------------------------");
+    println!("\nRotating Branch Keys\n");
+    examples::key_rotation::example(transport.clone()).await?;
 
     println!("\n---------------------------------------");
     println!("\nUsing Public Keys for Keyload Generation\n");
-    examples::pk_keyloads::example(url).await?;
+    examples::pk_keyloads::example(transport.clone()).await?;
 
     println!("\n---------------------------------------");
     println!("\nUsing Pre Shared Keys for Keyload Generation\n");
-    examples::psk_keyloads::example(url).await?;
+    examples::psk_keyloads::example(transport.clone()).await?;
 
     println!("\n---------------------------------------");
     println!("\nState Recovery\n");
-    examples::state_recovery::example(url).await?;
+    examples::state_recovery::example(transport.clone()).await?;
 
     println!("\n---------------------------------------");
     println!("\nStateless Recovery\n");
-    examples::stateless_recovery::example(url).await?;
+    examples::stateless_recovery::example(transport.clone()).await?;
+
+    println!("\n---------------------------------------");
+    println!("\nChannel Tree Visualisation\n");
+    let graph_path = std::env::temp_dir().join("channel.dot");
+    examples::channel_visualizer::example(transport.clone(), &graph_path).await?;
+
+    println!("\n---------------------------------------");
+    println!("\nChannel Archive Export and Import\n");
+    examples::channel_archive::example(transport.clone()).await?;
+
+    println!("\n---------------------------------------");
+    println!("\nBridging Channels\n");
+    examples::channel_bridge::example(transport.clone()).await?;
+
+    println!("\n---------------------------------------");
+    println!("\nScenario Files\n");
+    examples::scenario_runner::example(transport.clone()).await?;
+
+    println!("\n---------------------------------------");
+    println!("\nExpected Failures\n");
+    examples::failure_paths::example(transport.clone()).await?;
+
+    println!("\n---------------------------------------");
+    println!("\nNode Failover and Retries\n");
+    examples::node_failover::example().await?;
+
+    println!("\n---------------------------------------");
+    println!("\nPublishing Through an Offline Outbox\n");
+    examples::offline_outbox::example().await?;
+
+    println!("\n---------------------------------------");
+    println!("\nBatch Publishing\n");
+    examples::batch_publishing::example().await?;
+
+    println!("\n---------------------------------------");
+    println!("\nParallel Catch Up\n");
+    examples::parallel_catch_up::example().await?;
+
+    println!("\n---------------------------------------");
+    println!("\nSingle Depth Indexed Log\n");
+    examples::indexed_log::example().await?;
+
+    println!("\n---------------------------------------");
+    println!("\nChecking Channel Integrity\n");
+    examples::integrity_check::example().await?;
+
+    println!("\n---------------------------------------");
+    println!("\nResumable File Transfer\n");
+    examples::file_transfer::example().await?;
 
     println!("\n---------------------------------------");
     println!("Examples Complete");
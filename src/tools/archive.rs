@@ -0,0 +1,184 @@
+use anyhow::Result;
+use iota_streams::app_channels::api::tangle::{
+    Address, MessageContent, Transport, UnwrappedMessage,
+};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+
+use crate::{
+    tools::{visualizer::NodeKind, ChannelGraph, ChannelReader},
+    transport::{decode_message, encode_message, MemoryTransport},
+};
+
+// A payload as both base64 and, where the bytes are valid utf8, plain text
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Payload {
+    pub base64: String,
+    pub utf8: Option<String>,
+}
+
+impl Payload {
+    pub fn new(bytes: &[u8]) -> Option<Self> {
+        if bytes.is_empty() {
+            return None;
+        }
+        Some(Payload {
+            base64: base64::encode(bytes),
+            utf8: String::from_utf8(bytes.to_vec()).ok(),
+        })
+    }
+}
+
+// A readable message of the channel
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ArchiveEntry {
+    pub link: String,
+    pub prev_link: String,
+    pub msg_index: String,
+    pub msg_type: String,
+    pub publisher: Option<String>,
+    pub public_payload: Option<Payload>,
+    pub masked_payload: Option<Payload>,
+    // The keyload or announcement link the message's branch starts from
+    pub branch: Option<String>,
+}
+
+// The raw bytes of a message as stored on the transport, base64 encoded
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct RawMessage {
+    pub link: String,
+    pub bytes: String,
+}
+
+/**
+ * A JSON archive of a channel that can be handed to anyone without a Streams setup. The archive
+ * holds every message readable by the identity that exported it, alongside the raw messages
+ * (including sequencing messages) needed to load the channel back into an offline transport and
+ * re-read it.
+ */
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct ChannelArchive {
+    pub announcement: String,
+    pub exported_by: String,
+    pub messages: Vec<ArchiveEntry>,
+    pub raw_messages: Vec<RawMessage>,
+}
+
+impl ChannelArchive {
+    // Walk all messages readable by the reader, collecting their raw bytes from the transport
+    pub async fn export<R: ChannelReader, T: Transport>(
+        identity: &str,
+        announcement_link: &Address,
+        reader: &mut R,
+        transport: &mut T,
+    ) -> Result<Self> {
+        let mut archive = ChannelArchive {
+            announcement: announcement_link.to_string(),
+            exported_by: identity.to_string(),
+            ..Default::default()
+        };
+        let mut graph = ChannelGraph::new(announcement_link);
+        let mut seen = HashSet::new();
+        let mut retrieved: Vec<UnwrappedMessage> = Vec::new();
+
+        archive.store_raw(transport, announcement_link, &mut seen).await?;
+        loop {
+            // Sequencing messages are consumed while fetching and never returned. The links polled
+            // in a round that have moved on by the end of it were read, so those are stored, while
+            // links that stay put don't exist yet.
+            let polled = reader.next_msg_links();
+            let msgs = reader.fetch_next_msgs().await;
+            if msgs.is_empty() {
+                break;
+            }
+            let unread = reader.next_msg_links();
+            for link in polled.iter().filter(|link| !unread.contains(link)) {
+                archive.store_raw(transport, link, &mut seen).await?;
+            }
+            for msg in &msgs {
+                archive.store_raw(transport, &msg.link, &mut seen).await?;
+            }
+            graph.add_messages(identity, &msgs);
+            retrieved.extend(msgs);
+        }
+
+        archive.messages = retrieved
+            .iter()
+            .map(|msg| archive_entry(msg, &graph))
+            .collect();
+        Ok(archive)
+    }
+
+    async fn store_raw<T: Transport>(
+        &mut self,
+        transport: &mut T,
+        link: &Address,
+        seen: &mut HashSet<String>,
+    ) -> Result<()> {
+        if seen.contains(&link.to_string()) {
+            return Ok(());
+        }
+        for msg in transport.recv_messages(link).await? {
+            self.raw_messages.push(RawMessage {
+                link: link.to_string(),
+                bytes: base64::encode(encode_message(&msg)),
+            });
+        }
+        seen.insert(link.to_string());
+        Ok(())
+    }
+
+    pub fn to_json(&self) -> Result<String> {
+        Ok(serde_json::to_string_pretty(self)?)
+    }
+
+    pub fn from_json(json: &str) -> Result<Self> {
+        Ok(serde_json::from_str(json)?)
+    }
+
+    // Send the raw messages of the archive to a transport, returning the number of messages sent
+    pub async fn import<T: Transport>(&self, transport: &mut T) -> Result<usize> {
+        for raw in &self.raw_messages {
+            let msg = decode_message(&base64::decode(&raw.bytes)?)?;
+            transport.send_message(&msg).await?;
+        }
+        Ok(self.raw_messages.len())
+    }
+
+    // Load the archive into an in memory transport that can be shared by offline readers
+    pub async fn into_offline_transport(&self) -> Result<MemoryTransport> {
+        let transport = MemoryTransport::new();
+        self.import(&mut transport.clone()).await?;
+        Ok(transport)
+    }
+}
+
+fn archive_entry(msg: &UnwrappedMessage, graph: &ChannelGraph) -> ArchiveEntry {
+    let (publisher, public_payload, masked_payload) = match &msg.body {
+        MessageContent::SignedPacket {
+            pk,
+            public_payload,
+            masked_payload,
+        } => (
+            Some(hex::encode(pk.as_bytes())),
+            Payload::new(&public_payload.0),
+            Payload::new(&masked_payload.0),
+        ),
+        MessageContent::TaggedPacket {
+            public_payload,
+            masked_payload,
+        } => (None, Payload::new(&public_payload.0), Payload::new(&masked_payload.0)),
+        _ => (None, None, None),
+    };
+
+    ArchiveEntry {
+        link: msg.link.to_string(),
+        prev_link: msg.prev_link.to_string(),
+        msg_index: hex::encode(msg.link.to_msg_index()),
+        msg_type: NodeKind::from_content(&msg.body).name().to_string(),
+        publisher,
+        public_payload,
+        masked_payload,
+        branch: graph.branch_root(&msg.link).map(|root| root.to_string()),
+    }
+}
@@ -0,0 +1,116 @@
+use anyhow::{ensure, Result};
+use iota_streams::app_channels::api::tangle::{
+    Address, Author, Bytes, MessageContent, Subscriber, Transport,
+};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+
+use crate::tools::{
+    archive::Payload,
+    rotation::{send_keyload, Membership},
+};
+
+// Where a re-published message originally came from, carried as JSON in its public payload
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Provenance {
+    pub original_link: String,
+    // Hex encoded public key of the original publisher, tagged packets have none
+    pub publisher: Option<String>,
+    pub public_payload: Option<Payload>,
+}
+
+impl Provenance {
+    pub fn from_public_payload(public_payload: &Bytes) -> Result<Self> {
+        Ok(serde_json::from_slice(&public_payload.0)?)
+    }
+
+    pub fn to_public_payload(&self) -> Result<Bytes> {
+        Ok(Bytes(serde_json::to_vec(self)?))
+    }
+}
+
+// The branch created by a backfill, and the link each original message was re-published at
+#[derive(Clone, Debug)]
+pub struct Backfill {
+    pub keyload_link: Address,
+    pub republished: Vec<(Address, Address)>,
+    // The link to attach anything following the backfilled branch to
+    pub tip: Address,
+}
+
+/**
+ * Grant retroactive access to a branch by re-publishing its history. Access to a branch can't be
+ * extended once it has been keyloaded, so instead the source branch starting at `branch_root` is
+ * walked with `reader`, and the Author publishes each of its messages again in a new branch
+ * keyloaded for the new member set, attached to `link_to`. The masked payload is re-published
+ * untouched while the public payload is replaced by the provenance of the message (its original
+ * link, publisher and public payload), so readers can tell backfilled messages apart from live ones.
+ *
+ * The reader must have access to the source branch and must not have read past its start yet, such
+ * as a freshly started subscriber of the branch or a user state exported before it was published.
+ * It is a Subscriber because an Author never fetches the messages it published itself, so would
+ * find none of its own history. Only signed and tagged packets are re-published, while keyloads and
+ * subscriptions linked to the branch root, as they are to the announcement, start other branches
+ * and are left out.
+ */
+pub async fn backfill<T: Transport, S: Transport>(
+    author: &mut Author<T>,
+    reader: &mut Subscriber<S>,
+    branch_root: &Address,
+    link_to: &Address,
+    membership: &Membership,
+) -> Result<Backfill> {
+    // Read the whole history first, so that nothing is published if the branch can't be walked.
+    // Messages are fetched in order, so each message of the branch follows the one it links to.
+    let mut branch = HashSet::new();
+    branch.insert(branch_root.to_string());
+    let mut history = Vec::new();
+    for msg in reader.fetch_all_next_msgs().await {
+        if !branch.contains(&msg.prev_link.to_string()) {
+            continue;
+        }
+        branch.insert(msg.link.to_string());
+        let (publisher, public_payload, masked_payload) = match msg.body {
+            MessageContent::SignedPacket {
+                pk,
+                public_payload,
+                masked_payload,
+            } => (Some(hex::encode(pk.as_bytes())), public_payload, masked_payload),
+            MessageContent::TaggedPacket {
+                public_payload,
+                masked_payload,
+            } => (None, public_payload, masked_payload),
+            _ => continue,
+        };
+        let provenance = Provenance {
+            original_link: msg.link.to_string(),
+            publisher,
+            public_payload: Payload::new(&public_payload.0),
+        };
+        history.push((msg.link, provenance, masked_payload));
+    }
+    ensure!(
+        !history.is_empty(),
+        "The reader found no messages in the branch starting at {}", branch_root
+    );
+
+    let (keyload_link, seq_link) = send_keyload(author, link_to, membership).await?;
+
+    let mut backfill = Backfill {
+        tip: seq_link.unwrap_or_else(|| keyload_link.clone()),
+        keyload_link: keyload_link.clone(),
+        republished: Vec::new(),
+    };
+    let mut prev_msg_link = keyload_link;
+    for (original_link, provenance, masked_payload) in history {
+        let (msg_link, seq_link) = author.send_signed_packet(
+            &prev_msg_link,
+            &provenance.to_public_payload()?,
+            &masked_payload,
+        ).await?;
+        backfill.tip = seq_link.unwrap_or_else(|| msg_link.clone());
+        backfill.republished.push((original_link, msg_link.clone()));
+        prev_msg_link = msg_link;
+    }
+    Ok(backfill)
+}
@@ -0,0 +1,95 @@
+use anyhow::Result;
+use iota_streams::app_channels::api::tangle::{Address, Bytes, Transport};
+use std::fmt;
+
+use crate::{
+    tools::ChannelWriter,
+    transport::{BatchTransport, SubmitError},
+};
+
+/**
+ * A batch that was only partly published. The publishing user's state already covers every
+ * prepared packet, so the unsent packets stay queued on the transport and must be sent with
+ * `BatchTransport::submit` before anything else is published after them.
+ */
+#[derive(Debug)]
+pub struct BatchError {
+    // Message and sequencing links of the packets that reached the transport
+    pub sent: Vec<(Address, Option<Address>)>,
+    // Message and sequencing links of the packets still queued, in the order of the payloads
+    pub unsent: Vec<(Address, Option<Address>)>,
+    pub cause: anyhow::Error,
+}
+
+impl fmt::Display for BatchError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} of {} packets in the batch were not sent: {}",
+            self.unsent.len(), self.sent.len() + self.unsent.len(), self.cause
+        )
+    }
+}
+
+impl std::error::Error for BatchError {}
+
+/**
+ * Publish a list of masked payloads as a chain of signed packets attached to `link_to`, as a
+ * single batch. The user (which must be publishing over `transport`) prepares the whole chain
+ * while sending is deferred, and the messages are then submitted with at most `concurrency` sends
+ * in flight, instead of waiting for a round trip to the transport after every message.
+ *
+ * Returns the message and sequencing links of every packet, in the order of the payloads. If any
+ * message fails to send, a `BatchError` splits the packets into those sent and those whose packet
+ * or sequencing message is still queued on the transport.
+ */
+pub async fn publish_batch<W: ChannelWriter, T: Transport>(
+    writer: &mut W,
+    transport: &BatchTransport<T>,
+    link_to: &Address,
+    payloads: &[Bytes],
+    concurrency: usize,
+) -> Result<Vec<(Address, Option<Address>)>> {
+    transport.defer();
+    let mut links = Vec::new();
+    let mut prev_msg_link = link_to.clone();
+    for payload in payloads {
+        let prepared = writer.send_signed_packet(&prev_msg_link, &Bytes::default(), payload).await;
+        let (msg_link, seq_link) = match prepared {
+            Ok(links) => links,
+            Err(e) => {
+                // Whatever was prepared before the failure is still sent
+                submit(transport, concurrency, links).await?;
+                return Err(e);
+            }
+        };
+        prev_msg_link = msg_link.clone();
+        links.push((msg_link, seq_link));
+    }
+    submit(transport, concurrency, links).await
+}
+
+async fn submit<T: Transport>(
+    transport: &BatchTransport<T>,
+    concurrency: usize,
+    links: Vec<(Address, Option<Address>)>,
+) -> Result<Vec<(Address, Option<Address>)>> {
+    let e = match transport.submit(concurrency).await {
+        Ok(_) => return Ok(links),
+        Err(e) => e,
+    };
+    let failed = match e.downcast::<SubmitError>() {
+        Ok(failed) => failed,
+        Err(e) => return Err(e),
+    };
+    let (unsent, sent) = links.into_iter().partition(|(msg_link, seq_link)| {
+        failed.unsent.contains(msg_link)
+            || seq_link.as_ref().map_or(false, |seq_link| failed.unsent.contains(seq_link))
+    });
+    Err(BatchError {
+        sent,
+        unsent,
+        cause: failed.cause,
+    }
+    .into())
+}
@@ -0,0 +1,220 @@
+use anyhow::{anyhow, Result};
+use iota_streams::app_channels::api::tangle::{
+    Address, Author, Bytes, MessageContent, Subscriber, Transport, UnwrappedMessage,
+};
+use core::str::FromStr;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::BTreeSet,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use crate::tools::{write_atomic, ChannelWriter};
+
+// Everything needed to resume a bridge, written out after each mirrored message
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct Checkpoint {
+    // Exported Subscriber state for the source channel, as of the start of the last fetch round
+    pub source_state: String,
+    // Exported Author state for the destination channel
+    pub destination_state: String,
+    // The destination link the next mirrored message is attached to
+    pub prev_link: String,
+    // Source links of the current fetch round already handled, so that a round fetched again after
+    // a restart is not republished
+    pub mirrored: BTreeSet<String>,
+    // The message being mirrored when the checkpoint was written, recorded before it is sent
+    #[serde(default)]
+    pub pending: Option<PendingSend>,
+}
+
+// A source message and the destination link its copy is going to be published at
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct PendingSend {
+    pub source_link: String,
+    pub destination_link: String,
+}
+
+impl Checkpoint {
+    pub fn load(path: &Path) -> Result<Self> {
+        Ok(serde_json::from_str(&fs::read_to_string(path)?)?)
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        write_atomic(path, serde_json::to_string_pretty(self)?)
+    }
+}
+
+/**
+ * Mirrors messages from a source channel into a destination channel. The bridge reads the source
+ * channel as a Subscriber and republishes the messages accepted by a transform as signed packets
+ * from the Author of the destination channel, chained from `link_to`.
+ *
+ * A checkpoint holding both user states and the source links already mirrored is persisted after
+ * every republished message. The source state is only advanced once a whole fetch round has been
+ * mirrored, so a bridge resumed after a crash fetches any unfinished round again and skips the
+ * messages of it that were already mirrored, rather than losing or duplicating them. The link each
+ * copy is going to be published at is recorded before it is sent, so a crash between sending a copy
+ * and saving the checkpoint is detected on resume by finding a message already at that link.
+ */
+pub struct Bridge<S: Transport, D: Transport> {
+    source: Subscriber<S>,
+    destination: Author<D>,
+    checkpoint: Checkpoint,
+    checkpoint_path: PathBuf,
+    password: String,
+    // The source and destination links of a copy found to have been sent before the bridge stopped
+    recovered: Option<(Address, Address)>,
+}
+
+impl<S: Transport, D: Transport> Bridge<S, D> {
+    pub async fn new<P: AsRef<Path>>(
+        source: Subscriber<S>,
+        destination: Author<D>,
+        link_to: &Address,
+        checkpoint_path: P,
+        password: &str,
+    ) -> Result<Self> {
+        let mut bridge = Bridge {
+            source,
+            destination,
+            checkpoint: Checkpoint {
+                prev_link: link_to.to_string(),
+                ..Default::default()
+            },
+            checkpoint_path: checkpoint_path.as_ref().to_path_buf(),
+            password: password.to_string(),
+            recovered: None,
+        };
+        bridge.checkpoint.source_state = base64::encode(bridge.source.export(password).await?);
+        bridge.save().await?;
+        Ok(bridge)
+    }
+
+    // Restore a bridge from its persisted checkpoint
+    pub async fn resume<P: AsRef<Path>>(
+        checkpoint_path: P,
+        password: &str,
+        source_transport: S,
+        destination_transport: D,
+    ) -> Result<Self> {
+        let checkpoint = Checkpoint::load(checkpoint_path.as_ref())?;
+        let source = Subscriber::import(
+            &base64::decode(&checkpoint.source_state)?,
+            password,
+            source_transport,
+        ).await?;
+        let destination = Author::import(
+            &base64::decode(&checkpoint.destination_state)?,
+            password,
+            destination_transport.clone(),
+        ).await?;
+        let mut bridge = Bridge {
+            source,
+            destination,
+            checkpoint,
+            checkpoint_path: checkpoint_path.as_ref().to_path_buf(),
+            password: password.to_string(),
+            recovered: None,
+        };
+        bridge.settle_pending(destination_transport).await?;
+        Ok(bridge)
+    }
+
+    // Finish the record of a copy that was being sent when the bridge stopped. If it made it to the
+    // destination, the Author catches up with it and the source message counts as mirrored,
+    // otherwise it is mirrored again by the next run.
+    async fn settle_pending(&mut self, mut destination_transport: D) -> Result<()> {
+        let pending = match self.checkpoint.pending.take() {
+            Some(pending) => pending,
+            None => return Ok(()),
+        };
+        let destination_link = Address::from_str(&pending.destination_link)?;
+        // Transports report a link with nothing behind it as an error
+        let sent = destination_transport
+            .recv_messages(&destination_link)
+            .await
+            .map_or(false, |msgs| !msgs.is_empty());
+        if sent {
+            let msgs = self.destination.fetch_all_next_msgs().await;
+            let msg_link = msgs
+                .iter()
+                .rev()
+                .find(|msg| matches!(msg.body, MessageContent::SignedPacket { .. }))
+                .map(|msg| msg.link.clone())
+                .ok_or_else(|| anyhow!("No mirrored message found at {}", destination_link))?;
+            self.checkpoint.prev_link = msg_link.to_string();
+            self.recovered = Some((Address::from_str(&pending.source_link)?, msg_link));
+            self.checkpoint.mirrored.insert(pending.source_link);
+        }
+        self.save().await
+    }
+
+    // The copy that made it to the destination before the bridge stopped, if any
+    pub fn recovered(&self) -> Option<&(Address, Address)> {
+        self.recovered.as_ref()
+    }
+
+    pub fn checkpoint(&self) -> &Checkpoint {
+        &self.checkpoint
+    }
+
+    pub fn destination(&mut self) -> &mut Author<D> {
+        &mut self.destination
+    }
+
+    /**
+     * Fetch everything new in the source channel and mirror it, returning the source and destination
+     * links of each message republished. The transform decides what is mirrored by returning the
+     * public and masked payloads to publish, or `None` to skip the message.
+     */
+    pub async fn run<F>(&mut self, mut transform: F) -> Result<Vec<(Address, Address)>>
+    where
+        F: FnMut(&UnwrappedMessage) -> Option<(Bytes, Bytes)>,
+    {
+        let mut republished = Vec::new();
+        loop {
+            let msgs = self.source.fetch_next_msgs().await;
+            if msgs.is_empty() {
+                break;
+            }
+            for msg in &msgs {
+                let source_link = msg.link.to_string();
+                if self.checkpoint.mirrored.contains(&source_link) {
+                    continue;
+                }
+                if let Some((public_payload, masked_payload)) = transform(msg) {
+                    let prev_link = Address::from_str(&self.checkpoint.prev_link)?;
+                    self.checkpoint.pending = Some(PendingSend {
+                        source_link: source_link.clone(),
+                        destination_link: self.destination.next_msg_link()?.to_string(),
+                    });
+                    self.checkpoint.save(&self.checkpoint_path)?;
+                    let (msg_link, _seq) = self.destination.send_signed_packet(
+                        &prev_link,
+                        &public_payload,
+                        &masked_payload,
+                    ).await?;
+                    self.checkpoint.prev_link = msg_link.to_string();
+                    self.checkpoint.pending = None;
+                    republished.push((msg.link.clone(), msg_link));
+                }
+                self.checkpoint.mirrored.insert(source_link);
+                self.save().await?;
+            }
+
+            // The round is complete, the source state can move past it
+            self.checkpoint.source_state = base64::encode(self.source.export(&self.password).await?);
+            self.checkpoint.mirrored.clear();
+            self.save().await?;
+        }
+        Ok(republished)
+    }
+
+    async fn save(&mut self) -> Result<()> {
+        self.checkpoint.destination_state =
+            base64::encode(self.destination.export(&self.password).await?);
+        self.checkpoint.save(&self.checkpoint_path)
+    }
+}
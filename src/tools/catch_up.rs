@@ -0,0 +1,55 @@
+use iota_streams::app_channels::api::tangle::{
+    Address, MessageContent, Transport, UnwrappedMessage,
+};
+
+use crate::{tools::ChannelReader, transport::PrefetchTransport};
+
+// The messages read by a catch up, and the links that failed to prefetch along the way
+#[derive(Default)]
+pub struct CaughtUp {
+    pub msgs: Vec<UnwrappedMessage>,
+    pub failed: Vec<(Address, anyhow::Error)>,
+}
+
+/**
+ * Catch a reader up with a channel, fetching the cursors of independent publishers concurrently.
+ * Each round, the links the reader is going to poll next are prefetched through the reader's
+ * transport with at most `concurrency` requests in flight. The reader then processes the round,
+ * and whatever it didn't read is evicted from the transport before the next round. In multi branch
+ * channels those links are the sequence messages of each publisher, so the messages they point at
+ * are still requested by the reader one at a time. Links that failed to prefetch are left to the
+ * reader, which requests them again itself, and are reported with their errors. The reader must be
+ * reading over `transport`.
+ *
+ * The order in which a reader returns the messages of different publishers within a round is not
+ * fixed, so each round is sorted by publisher and link, making the result the same for every
+ * reader of the same channel.
+ */
+pub async fn catch_up<R: ChannelReader, T: Transport>(
+    reader: &mut R,
+    transport: &PrefetchTransport<T>,
+    concurrency: usize,
+) -> CaughtUp {
+    let mut caught_up = CaughtUp::default();
+    loop {
+        let prefetched = transport.prefetch(reader.next_msg_links(), concurrency).await;
+        caught_up.failed.extend(prefetched.failed);
+        let mut msgs = reader.fetch_next_msgs().await;
+        transport.evict();
+        if msgs.is_empty() {
+            break;
+        }
+        msgs.sort_by_key(merge_key);
+        caught_up.msgs.extend(msgs);
+    }
+    caught_up
+}
+
+// Messages are ordered by the key of their publisher, and then by link
+fn merge_key(msg: &UnwrappedMessage) -> (String, String) {
+    let publisher = match &msg.body {
+        MessageContent::SignedPacket { pk, .. } => hex::encode(pk.as_bytes()),
+        _ => String::new(),
+    };
+    (publisher, msg.link.to_string())
+}
@@ -0,0 +1,363 @@
+use anyhow::{anyhow, ensure, Result};
+use crypto::hashes::{blake2b::Blake2b256, Digest};
+use iota_streams::app_channels::api::tangle::{
+    Address, Bytes, MessageContent, Transport, UnwrappedMessage,
+};
+use core::str::FromStr;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+use crate::{
+    tools::{ChannelReader, ChannelWriter},
+    transport::PrefetchTransport,
+};
+
+// Public payloads marking signed packets as the chunks, manifest pages and manifest of a chunked
+// payload
+pub const CHUNK_TAG: &[u8] = b"chunked-payload-chunk";
+pub const MANIFEST_PAGE_TAG: &[u8] = b"chunked-payload-manifest-page";
+pub const MANIFEST_TAG: &[u8] = b"chunked-payload-manifest";
+// Default size of each chunk, well within what a single message can carry
+pub const DEFAULT_CHUNK_SIZE: usize = 1024;
+// Largest encoded manifest, or manifest page, published in a single message
+pub const MAX_MANIFEST_SIZE: usize = 4 * DEFAULT_CHUNK_SIZE;
+
+/**
+ * Describes a payload published across several signed packets. The manifest is published as a
+ * signed packet of its own after the chunks, holding the Blake2b-256 hash and size of the whole
+ * payload along with the links of every chunk, in order.
+ *
+ * Each chunk link takes up about a hundred bytes, so the links of a large payload don't fit in a
+ * single message. They are then published in pages chained between the last chunk and the
+ * manifest, each page linking back to the one before it, and the manifest only holds the link of
+ * the last page.
+ */
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Manifest {
+    pub hash: String,
+    pub size: usize,
+    pub chunk_count: usize,
+    pub chunk_links: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_page: Option<String>,
+}
+
+// A page of the chunk links of a manifest too large for a single message
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+struct ManifestPage {
+    chunk_links: Vec<String>,
+    prev_page: Option<String>,
+}
+
+impl ManifestPage {
+    fn from_message(msg: &UnwrappedMessage) -> Result<Self> {
+        match &msg.body {
+            MessageContent::SignedPacket { public_payload, masked_payload, .. }
+                if public_payload.0 == MANIFEST_PAGE_TAG =>
+            {
+                Ok(serde_json::from_slice(&masked_payload.0)?)
+            }
+            _ => Err(anyhow!("{} is not a manifest page", msg.link)),
+        }
+    }
+}
+
+impl Manifest {
+    pub fn new(hash: String, size: usize, chunk_links: Vec<String>) -> Self {
+        Manifest {
+            hash,
+            size,
+            chunk_count: chunk_links.len(),
+            chunk_links,
+            last_page: None,
+        }
+    }
+
+    // The manifest carried by a message, if it is one
+    pub fn from_message(msg: &UnwrappedMessage) -> Option<Self> {
+        match &msg.body {
+            MessageContent::SignedPacket { public_payload, masked_payload, .. }
+                if public_payload.0 == MANIFEST_TAG =>
+            {
+                serde_json::from_slice(&masked_payload.0).ok()
+            }
+            _ => None,
+        }
+    }
+
+    /**
+     * The groups of chunk links to publish as pages ahead of the manifest, in order, so that each
+     * page fits in a single message. Empty when the manifest fits in a single message as it is.
+     */
+    pub(crate) fn pages(&self) -> Result<Vec<Vec<String>>> {
+        if encode(self).is_ok() {
+            return Ok(Vec::new());
+        }
+        let mut pages = Vec::new();
+        let mut page = Vec::new();
+        for link in &self.chunk_links {
+            page.push(link.clone());
+            // Every page but the first also links back to the one before it, through a link as
+            // long as a chunk link
+            let prev_page = if pages.is_empty() { None } else { Some(link.clone()) };
+            if encode(&ManifestPage { chunk_links: page.clone(), prev_page }).is_err() {
+                page.pop();
+                ensure!(!page.is_empty(), "A single chunk link doesn't fit in a manifest page");
+                pages.push(page);
+                page = vec![link.clone()];
+            }
+        }
+        pages.push(page);
+        Ok(pages)
+    }
+
+    // The links of every chunk in order, given the pages of the manifest from the last one back
+    fn all_chunk_links(&self, pages: Vec<ManifestPage>) -> Result<Vec<Address>> {
+        let links = pages
+            .into_iter()
+            .rev()
+            .flat_map(|page| page.chunk_links)
+            .chain(self.chunk_links.iter().cloned())
+            .map(|link| Ok(Address::from_str(&link)?))
+            .collect::<Result<Vec<Address>>>()?;
+        ensure!(
+            links.len() == self.chunk_count,
+            "The manifest lists {} chunks, its pages link to {}", self.chunk_count, links.len()
+        );
+        Ok(links)
+    }
+
+    /**
+     * Reassemble the payload from messages already retrieved, such as those returned while
+     * syncing, and verify it against the manifest.
+     */
+    pub fn assemble(&self, msgs: &[UnwrappedMessage]) -> Result<Vec<u8>> {
+        let by_link: HashMap<String, &UnwrappedMessage> =
+            msgs.iter().map(|msg| (msg.link.to_string(), msg)).collect();
+        let mut pages = Vec::new();
+        let mut next_page = self.last_page.clone();
+        while let Some(link) = next_page {
+            ensure!(pages.len() < self.chunk_count, "The manifest pages link back in a loop");
+            let msg = by_link
+                .get(&link)
+                .ok_or_else(|| anyhow!("Manifest page {} has not been retrieved", link))?;
+            let page = ManifestPage::from_message(msg)?;
+            next_page = page.prev_page.clone();
+            pages.push(page);
+        }
+
+        let mut chunks = Vec::new();
+        for link in self.all_chunk_links(pages)? {
+            let link = link.to_string();
+            let msg = by_link
+                .get(&link)
+                .ok_or_else(|| anyhow!("Chunk {} has not been retrieved", link))?;
+            chunks.push(chunk_payload(msg)?);
+        }
+        self.verify(chunks)
+    }
+
+    // Join the chunks and check the result matches the manifest
+    pub(crate) fn verify(&self, chunks: Vec<Vec<u8>>) -> Result<Vec<u8>> {
+        ensure!(
+            chunks.len() == self.chunk_count,
+            "Expected {} chunks, got {}", self.chunk_count, chunks.len()
+        );
+        let payload = chunks.concat();
+        ensure!(
+            payload.len() == self.size,
+            "Reassembled payload is {} bytes, expected {}", payload.len(), self.size
+        );
+        ensure!(hash(&payload) == self.hash, "Reassembled payload does not match the manifest hash");
+        Ok(payload)
+    }
+}
+
+/**
+ * Publish a payload too large for a single message as a chain of signed packets attached to
+ * `link_to`, each tagged as a chunk and holding up to `chunk_size` bytes of it, followed by a
+ * manifest linked to the last chunk, or to the last of its pages. Returns the link of the
+ * manifest, which is all a reader needs to reassemble the payload, along with the last sequencing
+ * link in multi branch channels.
+ */
+pub async fn publish_chunked<W: ChannelWriter>(
+    writer: &mut W,
+    link_to: &Address,
+    payload: &[u8],
+    chunk_size: usize,
+) -> Result<(Address, Option<Address>)> {
+    let mut chunk_links = Vec::new();
+    let mut prev_msg_link = link_to.clone();
+    for chunk in payload.chunks(chunk_size.max(1)) {
+        let (msg_link, _seq_link) = send_chunk(writer, &prev_msg_link, chunk).await?;
+        chunk_links.push(msg_link.to_string());
+        prev_msg_link = msg_link;
+    }
+
+    let mut manifest = Manifest::new(hash(payload), payload.len(), chunk_links);
+    let pages = manifest.pages()?;
+    for page_links in &pages {
+        let (msg_link, _seq_link) =
+            send_page(writer, &prev_msg_link, page_links, manifest.last_page.as_deref()).await?;
+        manifest.last_page = Some(msg_link.to_string());
+        prev_msg_link = msg_link;
+    }
+    if !pages.is_empty() {
+        manifest.chunk_links.clear();
+    }
+    send_manifest(writer, &prev_msg_link, &manifest).await
+}
+
+pub(crate) async fn send_chunk<W: ChannelWriter>(
+    writer: &mut W,
+    link_to: &Address,
+    chunk: &[u8],
+) -> Result<(Address, Option<Address>)> {
+    writer.send_signed_packet(link_to, &Bytes(CHUNK_TAG.to_vec()), &Bytes(chunk.to_vec())).await
+}
+
+pub(crate) async fn send_page<W: ChannelWriter>(
+    writer: &mut W,
+    link_to: &Address,
+    chunk_links: &[String],
+    prev_page: Option<&str>,
+) -> Result<(Address, Option<Address>)> {
+    let page = ManifestPage {
+        chunk_links: chunk_links.to_vec(),
+        prev_page: prev_page.map(str::to_string),
+    };
+    writer.send_signed_packet(
+        link_to,
+        &Bytes(MANIFEST_PAGE_TAG.to_vec()),
+        &Bytes(encode(&page)?),
+    ).await
+}
+
+pub(crate) async fn send_manifest<W: ChannelWriter>(
+    writer: &mut W,
+    link_to: &Address,
+    manifest: &Manifest,
+) -> Result<(Address, Option<Address>)> {
+    writer.send_signed_packet(
+        link_to,
+        &Bytes(MANIFEST_TAG.to_vec()),
+        &Bytes(encode(manifest)?),
+    ).await
+}
+
+// A manifest or page as JSON, refused when it is too large to publish in a single message
+fn encode<M: Serialize>(manifest: &M) -> Result<Vec<u8>> {
+    let encoded = serde_json::to_vec(manifest)?;
+    ensure!(
+        encoded.len() <= MAX_MANIFEST_SIZE,
+        "Manifest is {} bytes, more than the {} bytes published in a single message",
+        encoded.len(), MAX_MANIFEST_SIZE
+    );
+    Ok(encoded)
+}
+
+/**
+ * Receive the manifest at `manifest_link` and each of its chunks, returning the verified payload.
+ * The reader must be reading over `transport`.
+ *
+ * The manifest and its pages are read one after the other, since each links to the one before.
+ * Once every chunk link is known, the chunks are prefetched with at most `concurrency` requests in
+ * flight, and then read from the prefetched messages.
+ */
+pub async fn receive_chunked<R: ChannelReader, T: Transport>(
+    reader: &mut R,
+    transport: &PrefetchTransport<T>,
+    manifest_link: &Address,
+    concurrency: usize,
+) -> Result<Vec<u8>> {
+    let msg = reader.receive_msg(manifest_link).await?;
+    let manifest = Manifest::from_message(&msg)
+        .ok_or_else(|| anyhow!("{} is not the manifest of a chunked payload", manifest_link))?;
+
+    let mut pages = Vec::new();
+    let mut next_page = manifest.last_page.clone();
+    while let Some(link) = next_page {
+        ensure!(pages.len() < manifest.chunk_count, "The manifest pages link back in a loop");
+        let msg = reader.receive_msg(&Address::from_str(&link)?).await?;
+        let page = ManifestPage::from_message(&msg)?;
+        next_page = page.prev_page.clone();
+        pages.push(page);
+    }
+    let chunk_links = manifest.all_chunk_links(pages)?;
+
+    // Chunks whose prefetch failed are requested again as they are read, and chunks left unread
+    // after a failure are dropped from the cache
+    transport.prefetch(chunk_links.clone(), concurrency).await;
+    let chunks = receive_chunks(reader, &chunk_links).await;
+    transport.evict();
+    manifest.verify(chunks?)
+}
+
+async fn receive_chunks<R: ChannelReader>(
+    reader: &mut R,
+    links: &[Address],
+) -> Result<Vec<Vec<u8>>> {
+    let mut chunks = Vec::new();
+    for link in links {
+        chunks.push(chunk_payload(&reader.receive_msg(link).await?)?);
+    }
+    Ok(chunks)
+}
+
+// The bytes carried by a chunk. Chunks published before chunks were tagged have an empty public
+// payload, and are read all the same.
+pub(crate) fn chunk_payload(msg: &UnwrappedMessage) -> Result<Vec<u8>> {
+    match &msg.body {
+        MessageContent::SignedPacket { public_payload, masked_payload, .. }
+            if public_payload.0 == CHUNK_TAG || public_payload.0.is_empty() =>
+        {
+            Ok(masked_payload.0.clone())
+        }
+        _ => Err(anyhow!("{} is not a chunk", msg.link)),
+    }
+}
+
+pub(crate) fn hash(payload: &[u8]) -> String {
+    hex::encode(Blake2b256::digest(payload))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Stand-in links as long as the links of a channel
+    fn links(count: usize) -> Vec<String> {
+        (0..count).map(|i| format!("{:0>80}:{:0>24}", i, i)).collect()
+    }
+
+    #[test]
+    fn small_manifest_is_not_paged() {
+        let manifest = Manifest::new(hash(b"payload"), 7, links(3));
+        assert!(manifest.pages().unwrap().is_empty());
+    }
+
+    #[test]
+    fn large_manifest_is_paged_in_order() {
+        let chunk_links = links(200);
+        let manifest = Manifest::new(hash(b"payload"), 7, chunk_links.clone());
+        assert!(encode(&manifest).is_err());
+
+        let pages = manifest.pages().unwrap();
+        assert!(pages.len() > 1);
+        for (i, page_links) in pages.iter().enumerate() {
+            let page = ManifestPage {
+                chunk_links: page_links.clone(),
+                prev_page: if i == 0 { None } else { Some(chunk_links[0].clone()) },
+            };
+            assert!(encode(&page).is_ok(), "Page {} does not fit in a single message", i);
+        }
+        assert_eq!(pages.concat(), chunk_links);
+    }
+
+    #[test]
+    fn oversize_manifest_is_refused() {
+        let manifest = Manifest::new(hash(b"payload"), 7, links(200));
+        let error = encode(&manifest).unwrap_err().to_string();
+        assert!(error.contains("single message"), "Unexpected error: {}", error);
+    }
+}
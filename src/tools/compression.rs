@@ -0,0 +1,310 @@
+use anyhow::{bail, Result};
+use async_trait::async_trait;
+use flate2::{read::DeflateDecoder, write::DeflateEncoder, Compression};
+use iota_streams::app_channels::api::tangle::{Address, Bytes, MessageContent, UnwrappedMessage};
+use std::{
+    fmt,
+    io::{Read, Write},
+};
+
+use crate::tools::{ChannelReader, ChannelWriter};
+
+// Opens the public payload of a packet whose masked payload is compressed. The flag is kept out of
+// the masked payload, so that a masked payload of any bytes is never mistaken for a compressed one.
+pub const COMPRESSION_TAG: &[u8] = b"compressed-payload";
+// The tag followed by a single byte identifying the codec, ahead of the original public payload
+pub const HEADER_LEN: usize = COMPRESSION_TAG.len() + 1;
+// Compression level used for zstd, its own default
+const ZSTD_LEVEL: i32 = 3;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Codec {
+    Zstd,
+    Deflate,
+}
+
+impl Codec {
+    // The byte identifying the codec in the header
+    pub fn id(self) -> u8 {
+        match self {
+            Codec::Zstd => 1,
+            Codec::Deflate => 2,
+        }
+    }
+
+    pub fn from_id(id: u8) -> Option<Self> {
+        match id {
+            1 => Some(Codec::Zstd),
+            2 => Some(Codec::Deflate),
+            _ => None,
+        }
+    }
+
+    fn encode(self, payload: &[u8]) -> Result<Vec<u8>> {
+        match self {
+            Codec::Zstd => Ok(zstd::encode_all(payload, ZSTD_LEVEL)?),
+            Codec::Deflate => {
+                let mut encoder = DeflateEncoder::new(Vec::new(), Compression::default());
+                encoder.write_all(payload)?;
+                Ok(encoder.finish()?)
+            }
+        }
+    }
+
+    fn decode(self, data: &[u8]) -> Result<Vec<u8>> {
+        match self {
+            Codec::Zstd => Ok(zstd::decode_all(data)?),
+            Codec::Deflate => {
+                let mut payload = Vec::new();
+                DeflateDecoder::new(data).read_to_end(&mut payload)?;
+                Ok(payload)
+            }
+        }
+    }
+}
+
+impl fmt::Display for Codec {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Codec::Zstd => write!(f, "zstd"),
+            Codec::Deflate => write!(f, "deflate"),
+        }
+    }
+}
+
+// Whether a public payload flags the masked payload of its packet as compressed, whether or not its
+// codec is known
+pub fn is_compressed(public_payload: &[u8]) -> bool {
+    public_payload.len() >= HEADER_LEN && public_payload.starts_with(COMPRESSION_TAG)
+}
+
+// The codec id in the header of a public payload flagging a compressed packet
+pub fn codec_id(public_payload: &[u8]) -> Option<u8> {
+    if is_compressed(public_payload) {
+        Some(public_payload[COMPRESSION_TAG.len()])
+    } else {
+        None
+    }
+}
+
+/**
+ * Compress the masked payload of a packet with `codec`, and flag it in the public payload with a
+ * header naming the codec, ahead of the original public payload. Returns the public and masked
+ * payloads to publish. Masked payloads that wouldn't get any smaller, such as short or already
+ * compressed ones, are published as they are, which `decompress` passes through untouched.
+ */
+pub fn compress(
+    codec: Codec,
+    public_payload: &[u8],
+    masked_payload: &[u8],
+) -> Result<(Vec<u8>, Vec<u8>)> {
+    let compressed = codec.encode(masked_payload)?;
+    // A public payload that happens to start with the tag still needs a header to be read back
+    if compressed.len() >= masked_payload.len() && !is_compressed(public_payload) {
+        return Ok((public_payload.to_vec(), masked_payload.to_vec()));
+    }
+    let mut flagged = COMPRESSION_TAG.to_vec();
+    flagged.push(codec.id());
+    flagged.extend(public_payload);
+    Ok((flagged, compressed))
+}
+
+/**
+ * Decompress the masked payload of a packet according to the header of its public payload,
+ * returning the original public and masked payloads. Packets without a header are returned
+ * unchanged.
+ */
+pub fn decompress(public_payload: &[u8], masked_payload: &[u8]) -> Result<(Vec<u8>, Vec<u8>)> {
+    let id = match codec_id(public_payload) {
+        Some(id) => id,
+        None => return Ok((public_payload.to_vec(), masked_payload.to_vec())),
+    };
+    match Codec::from_id(id) {
+        Some(codec) => Ok((public_payload[HEADER_LEN..].to_vec(), codec.decode(masked_payload)?)),
+        None => bail!("Unknown compression codec {}", id),
+    }
+}
+
+// Decompress the payloads of a packet in place, leaving any other message, or a packet that fails
+// to decompress, as it is
+pub fn decompress_message(msg: &mut UnwrappedMessage) -> Result<()> {
+    match &mut msg.body {
+        MessageContent::SignedPacket { public_payload, masked_payload, .. }
+        | MessageContent::TaggedPacket { public_payload, masked_payload }
+            if is_compressed(&public_payload.0) =>
+        {
+            let (public, masked) = decompress(&public_payload.0, &masked_payload.0)?;
+            public_payload.0 = public;
+            masked_payload.0 = masked;
+        }
+        _ => (),
+    }
+    Ok(())
+}
+
+/**
+ * Publishes through another writer, compressing the masked payload of every packet with a chosen
+ * codec and flagging it in the public payload. Any tool taking a `ChannelWriter` can publish
+ * compressed payloads through it, such as `publish_chunked` or `send_tagged_packets`.
+ */
+pub struct CompressingWriter<'a, W> {
+    writer: &'a mut W,
+    codec: Codec,
+}
+
+impl<'a, W: ChannelWriter> CompressingWriter<'a, W> {
+    pub fn new(writer: &'a mut W, codec: Codec) -> Self {
+        CompressingWriter { writer, codec }
+    }
+
+    pub fn codec(&self) -> Codec {
+        self.codec
+    }
+}
+
+#[async_trait(?Send)]
+impl<'a, W: ChannelWriter> ChannelWriter for CompressingWriter<'a, W> {
+    async fn send_signed_packet(
+        &mut self,
+        link_to: &Address,
+        public_payload: &Bytes,
+        masked_payload: &Bytes,
+    ) -> Result<(Address, Option<Address>)> {
+        let (public_payload, masked_payload) =
+            compress(self.codec, &public_payload.0, &masked_payload.0)?;
+        self.writer.send_signed_packet(link_to, &Bytes(public_payload), &Bytes(masked_payload))
+            .await
+    }
+
+    async fn send_tagged_packet(
+        &mut self,
+        link_to: &Address,
+        public_payload: &Bytes,
+        masked_payload: &Bytes,
+    ) -> Result<(Address, Option<Address>)> {
+        let (public_payload, masked_payload) =
+            compress(self.codec, &public_payload.0, &masked_payload.0)?;
+        self.writer.send_tagged_packet(link_to, &Bytes(public_payload), &Bytes(masked_payload))
+            .await
+    }
+
+    async fn export(&self, password: &str) -> Result<Vec<u8>> {
+        self.writer.export(password).await
+    }
+
+    fn next_msg_link(&mut self) -> Result<Address> {
+        self.writer.next_msg_link()
+    }
+}
+
+/**
+ * Reads through another reader, decompressing the payloads of every packet according to the header
+ * of its public payload, so that compressed and uncompressed packets read the same. Messages
+ * received directly fail if they can't be decompressed, while those fetched in bulk are returned
+ * with their payloads still compressed, leaving the caller to spot them with `is_compressed` and
+ * skip them.
+ */
+pub struct DecompressingReader<'a, R> {
+    reader: &'a mut R,
+}
+
+impl<'a, R: ChannelReader> DecompressingReader<'a, R> {
+    pub fn new(reader: &'a mut R) -> Self {
+        DecompressingReader { reader }
+    }
+}
+
+// Decompress what can be, leaving messages with unknown codecs or corrupt payloads untouched
+fn decompress_all(mut msgs: Vec<UnwrappedMessage>) -> Vec<UnwrappedMessage> {
+    for msg in &mut msgs {
+        let _ = decompress_message(msg);
+    }
+    msgs
+}
+
+fn decompressed(mut msg: UnwrappedMessage) -> Result<UnwrappedMessage> {
+    decompress_message(&mut msg)?;
+    Ok(msg)
+}
+
+#[async_trait(?Send)]
+impl<'a, R: ChannelReader> ChannelReader for DecompressingReader<'a, R> {
+    async fn fetch_next_msgs(&mut self) -> Vec<UnwrappedMessage> {
+        decompress_all(self.reader.fetch_next_msgs().await)
+    }
+
+    async fn fetch_all_next_msgs(&mut self) -> Vec<UnwrappedMessage> {
+        decompress_all(self.reader.fetch_all_next_msgs().await)
+    }
+
+    fn next_publisher_links(&mut self) -> Vec<(String, Address)> {
+        self.reader.next_publisher_links()
+    }
+
+    async fn fetch_prev_msg(&mut self, link: &Address) -> Result<UnwrappedMessage> {
+        decompressed(self.reader.fetch_prev_msg(link).await?)
+    }
+
+    async fn receive_msg(&mut self, link: &Address) -> Result<UnwrappedMessage> {
+        decompressed(self.reader.receive_msg(link).await?)
+    }
+
+    async fn receive_msg_by_sequence_number(
+        &mut self,
+        anchor: &Address,
+        msg_num: u32,
+    ) -> Result<UnwrappedMessage> {
+        decompressed(self.reader.receive_msg_by_sequence_number(anchor, msg_num).await?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::{collection::vec, prelude::*};
+
+    fn codec() -> impl Strategy<Value = Codec> {
+        prop_oneof![Just(Codec::Zstd), Just(Codec::Deflate)]
+    }
+
+    // Masked payloads of random bytes, which don't compress, and of repetitive ones, which do
+    fn masked_payload() -> impl Strategy<Value = Vec<u8>> {
+        prop_oneof![vec(any::<u8>(), 0..256), vec(0u8..4, 0..2048)]
+    }
+
+    proptest! {
+        #[test]
+        fn compressed_packets_read_back(
+            codec in codec(),
+            public_payload in vec(any::<u8>(), 0..64),
+            masked_payload in masked_payload(),
+        ) {
+            let (flagged, compressed) = compress(codec, &public_payload, &masked_payload).unwrap();
+            prop_assert_eq!(
+                decompress(&flagged, &compressed).unwrap(),
+                (public_payload, masked_payload)
+            );
+        }
+
+        #[test]
+        fn unflagged_packets_read_as_they_are(
+            public_payload in vec(any::<u8>(), 0..64),
+            masked_payload in masked_payload(),
+        ) {
+            prop_assume!(!is_compressed(&public_payload));
+            prop_assert_eq!(
+                decompress(&public_payload, &masked_payload).unwrap(),
+                (public_payload, masked_payload)
+            );
+        }
+    }
+
+    #[test]
+    fn binary_masked_payload_is_not_taken_as_compressed() {
+        // Starts with what used to be sniffed as a compression header
+        let masked_payload = vec![0xFF, b'S', b'Z', 1, 0x28, 0xB5, 0x2F, 0xFD];
+        let (public, masked) = decompress(&[], &masked_payload).unwrap();
+        assert!(public.is_empty());
+        assert_eq!(masked, masked_payload);
+    }
+}
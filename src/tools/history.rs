@@ -0,0 +1,188 @@
+use anyhow::{bail, Result};
+use futures::stream::{self, Stream};
+use iota_streams::app_channels::api::tangle::{Address, MessageContent, UnwrappedMessage};
+use std::collections::VecDeque;
+
+use crate::tools::ChannelReader;
+
+/**
+ * Walks a chain of messages backwards from a link, one page at a time. Each page holds up to
+ * `page_size` messages, newest first, and only the messages of that page are retrieved. The walk
+ * stops once it reaches the announcement or a keyload, since nothing before a keyload can be
+ * assumed readable with the keys it grants. The link of the message it stopped at is kept as the
+ * boundary. The reader must already hold the keys of the messages it walks over, for instance by
+ * having synced its state. A fetch that fails leaves the walk where it was, keeping the messages of
+ * the page retrieved so far, so calling `next_page` again retries from the same message.
+ */
+pub struct BackwardPages<'a, R> {
+    reader: &'a mut R,
+    next_link: Option<Address>,
+    page_size: usize,
+    boundary: Option<Address>,
+    // The messages of a page whose retrieval was interrupted by an error
+    partial: Vec<UnwrappedMessage>,
+}
+
+impl<'a, R: ChannelReader> BackwardPages<'a, R> {
+    // Start before `from`, which is not itself part of any page
+    pub fn new(reader: &'a mut R, from: &Address, page_size: usize) -> Self {
+        BackwardPages {
+            reader,
+            next_link: Some(from.clone()),
+            page_size: page_size.max(1),
+            boundary: None,
+            partial: Vec::new(),
+        }
+    }
+
+    // The announcement or keyload the walk stopped at, once it has been reached
+    pub fn boundary(&self) -> Option<&Address> {
+        self.boundary.as_ref()
+    }
+
+    pub async fn next_page(&mut self) -> Result<Option<Vec<UnwrappedMessage>>> {
+        let mut page = std::mem::take(&mut self.partial);
+        while page.len() < self.page_size {
+            let link = match &self.next_link {
+                Some(link) => link.clone(),
+                None => break,
+            };
+            let msg = match self.reader.fetch_prev_msg(&link).await {
+                Ok(msg) => msg,
+                Err(e) => {
+                    self.partial = page;
+                    return Err(e);
+                }
+            };
+            match msg.body {
+                MessageContent::Announce | MessageContent::Keyload => {
+                    self.next_link = None;
+                    self.boundary = Some(msg.link);
+                }
+                _ => {
+                    self.next_link = Some(msg.link.clone());
+                    page.push(msg);
+                }
+            }
+        }
+        Ok(if page.is_empty() { None } else { Some(page) })
+    }
+
+    pub fn into_stream(self) -> impl Stream<Item = Result<Vec<UnwrappedMessage>>> + 'a
+    where
+        R: 'a,
+    {
+        stream::unfold(self, |mut pages| async move {
+            pages.next_page().await.transpose().map(|page| (page, pages))
+        })
+    }
+}
+
+/**
+ * Walks a channel forwards from the reader's current position, one page at a time. Each page holds
+ * up to `page_size` messages in the order the reader retrieves them. When started after a link,
+ * every message up to and including that link is skipped, so the reader must not already be past
+ * it. If the walk reaches the end of the channel without coming across the link, an error is
+ * returned instead of an empty history.
+ */
+pub struct ForwardPages<'a, R> {
+    reader: &'a mut R,
+    after: Option<Address>,
+    page_size: usize,
+    buffered: VecDeque<UnwrappedMessage>,
+    exhausted: bool,
+}
+
+impl<'a, R: ChannelReader> ForwardPages<'a, R> {
+    pub fn new(reader: &'a mut R, page_size: usize) -> Self {
+        ForwardPages {
+            reader,
+            after: None,
+            page_size: page_size.max(1),
+            buffered: VecDeque::new(),
+            exhausted: false,
+        }
+    }
+
+    pub fn after(reader: &'a mut R, link: &Address, page_size: usize) -> Self {
+        let mut pages = ForwardPages::new(reader, page_size);
+        pages.after = Some(link.clone());
+        pages
+    }
+
+    pub async fn next_page(&mut self) -> Result<Option<Vec<UnwrappedMessage>>> {
+        while self.buffered.len() < self.page_size && !self.exhausted {
+            let msgs = self.reader.fetch_next_msgs().await;
+            if msgs.is_empty() {
+                self.exhausted = true;
+            }
+            for msg in msgs {
+                match &self.after {
+                    Some(after) => {
+                        if &msg.link == after {
+                            self.after = None;
+                        }
+                    }
+                    None => self.buffered.push_back(msg),
+                }
+            }
+        }
+
+        if let Some(after) = &self.after {
+            bail!("Reached the end of the channel without finding {} to start after", after);
+        }
+
+        let len = self.buffered.len().min(self.page_size);
+        if len == 0 {
+            return Ok(None);
+        }
+        Ok(Some(self.buffered.drain(..len).collect()))
+    }
+
+    pub fn into_stream(self) -> impl Stream<Item = Result<Vec<UnwrappedMessage>>> + 'a
+    where
+        R: 'a,
+    {
+        stream::unfold(self, |mut pages| async move {
+            pages.next_page().await.transpose().map(|page| (page, pages))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::transport::{MemoryTransport, StandInNode};
+    use futures::executor::block_on;
+    use iota_streams::app_channels::api::tangle::{Author, Bytes, ChannelType, Subscriber};
+
+    #[test]
+    fn failed_page_is_retried_from_the_same_message() {
+        block_on(async {
+            let node = StandInNode::new(MemoryTransport::new());
+            let mut author = Author::new("PagingAuthor", ChannelType::SingleBranch, node.clone());
+            let announcement_link = author.send_announce().await?;
+            let mut prev_link = announcement_link.clone();
+            for input in &["First", "Second", "Third"] {
+                let payload = Bytes(input.as_bytes().to_vec());
+                let (msg_link, _seq) =
+                    author.send_signed_packet(&prev_link, &Bytes::default(), &payload).await?;
+                prev_link = msg_link;
+            }
+            let mut subscriber = Subscriber::new("PagingReader", node.clone());
+            subscriber.receive_announcement(&announcement_link).await?;
+            subscriber.fetch_all_next_msgs().await;
+
+            let mut pages = BackwardPages::new(&mut subscriber, &prev_link, 2);
+            node.go_down();
+            assert!(pages.next_page().await.is_err());
+            node.come_up();
+            let page = pages.next_page().await?.expect("The walk was lost after a failed fetch");
+            assert_eq!(page.len(), 2);
+            assert!(pages.next_page().await?.is_none());
+            assert_eq!(pages.boundary(), Some(&announcement_link));
+            Ok::<(), anyhow::Error>(())
+        })
+        .unwrap();
+    }
+}
@@ -0,0 +1,173 @@
+use anyhow::{anyhow, Result};
+use iota_streams::app_channels::api::tangle::{Address, MessageContent, UnwrappedMessage};
+use core::str::FromStr;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::HashMap,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use crate::tools::{write_atomic, ChannelReader};
+
+// Where a message sits in the channel, by its position in the order the reader synced it. The
+// position is local to the index and is not a Streams sequence number.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct IndexEntry {
+    #[serde(alias = "seq")]
+    pub position: usize,
+    pub link: String,
+    pub msg_index: String,
+    pub publisher: Option<String>,
+}
+
+impl IndexEntry {
+    pub fn link(&self) -> Result<Address> {
+        Ok(Address::from_str(&self.link)?)
+    }
+}
+
+/**
+ * A locally persisted index of a Single Branch channel, kept up to date by a reader as it syncs.
+ * Every message the reader retrieves is recorded with its position in the index, link, Tangle
+ * index and publisher, so that message N, or the k-th message of a given publisher, can be
+ * received directly by its link rather than by walking the chain again from the start. Messages
+ * are indexed once by link, so a reader that retrieves them again, such as one restored from an
+ * older state, doesn't add duplicates.
+ *
+ * The index only records what the reader has read, so the reader's own state should be exported
+ * alongside it to carry on syncing from the same point after a restart.
+ */
+#[derive(Debug)]
+pub struct MessageIndex {
+    path: PathBuf,
+    entries: Vec<IndexEntry>,
+    // Positions of the messages of each publisher, and of each link, rebuilt when the index is
+    // opened
+    by_publisher: HashMap<String, Vec<usize>>,
+    by_link: HashMap<String, usize>,
+}
+
+impl MessageIndex {
+    // Open the index stored at `path`, or start an empty one
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let path = path.as_ref().to_path_buf();
+        let entries: Vec<IndexEntry> = if path.exists() {
+            serde_json::from_str(&fs::read_to_string(&path)?)?
+        } else {
+            Vec::new()
+        };
+
+        let mut index = MessageIndex {
+            path,
+            entries: Vec::new(),
+            by_publisher: HashMap::new(),
+            by_link: HashMap::new(),
+        };
+        for entry in entries {
+            index.push(entry);
+        }
+        index.save()?;
+        Ok(index)
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn get(&self, position: usize) -> Option<&IndexEntry> {
+        self.entries.get(position)
+    }
+
+    pub fn position_of(&self, link: &Address) -> Option<usize> {
+        self.by_link.get(&link.to_string()).copied()
+    }
+
+    // The k-th message of a publisher, counting from 0, with the publisher given as a hex key
+    pub fn publisher_msg(&self, publisher: &str, k: usize) -> Option<&IndexEntry> {
+        self.by_publisher
+            .get(publisher)
+            .and_then(|positions| positions.get(k))
+            .map(|&position| &self.entries[position])
+    }
+
+    pub fn publisher_msg_count(&self, publisher: &str) -> usize {
+        self.by_publisher.get(publisher).map_or(0, Vec::len)
+    }
+
+    /**
+     * Fetch every new message available to the reader, recording each one not already indexed,
+     * and persist the index. Returns the messages retrieved, so the reader can be synced through
+     * the index without losing them.
+     */
+    pub async fn sync<R: ChannelReader>(
+        &mut self,
+        reader: &mut R,
+    ) -> Result<Vec<UnwrappedMessage>> {
+        let msgs = reader.fetch_all_next_msgs().await;
+        for msg in &msgs {
+            if self.position_of(&msg.link).is_some() {
+                continue;
+            }
+            let publisher = match &msg.body {
+                MessageContent::SignedPacket { pk, .. } => Some(hex::encode(pk.as_bytes())),
+                _ => None,
+            };
+            self.push(IndexEntry {
+                position: self.entries.len(),
+                link: msg.link.to_string(),
+                msg_index: hex::encode(msg.link.to_msg_index()),
+                publisher,
+            });
+        }
+        self.save()?;
+        Ok(msgs)
+    }
+
+    // Receive the message at `position` directly by its indexed link
+    pub async fn receive<R: ChannelReader>(
+        &self,
+        reader: &mut R,
+        position: usize,
+    ) -> Result<UnwrappedMessage> {
+        let entry = self.get(position).ok_or_else(|| {
+            anyhow!("Message {} has not been indexed, {} are", position, self.len())
+        })?;
+        reader.receive_msg(&entry.link()?).await
+    }
+
+    // Receive the k-th message of a publisher directly by its indexed link
+    pub async fn receive_publisher_msg<R: ChannelReader>(
+        &self,
+        reader: &mut R,
+        publisher: &str,
+        k: usize,
+    ) -> Result<UnwrappedMessage> {
+        let entry = self.publisher_msg(publisher, k).ok_or_else(|| {
+            anyhow!(
+                "Message {} of publisher {} has not been indexed, {} are",
+                k, publisher, self.publisher_msg_count(publisher)
+            )
+        })?;
+        reader.receive_msg(&entry.link()?).await
+    }
+
+    fn push(&mut self, entry: IndexEntry) {
+        if let Some(publisher) = &entry.publisher {
+            self.by_publisher
+                .entry(publisher.clone())
+                .or_default()
+                .push(entry.position);
+        }
+        self.by_link.insert(entry.link.clone(), entry.position);
+        self.entries.push(entry);
+    }
+
+    fn save(&self) -> Result<()> {
+        write_atomic(&self.path, serde_json::to_string_pretty(&self.entries)?)
+    }
+}
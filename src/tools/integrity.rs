@@ -0,0 +1,228 @@
+use anyhow::Result;
+use iota_streams::app_channels::api::tangle::{
+    Address, MessageContent, Subscriber, Transport, UnwrappedMessage,
+};
+use std::{collections::HashSet, fmt};
+
+use crate::{tools::ChannelReader, transport::is_not_found};
+
+// Sequence numbers probed past the last message found on an anchor before giving up
+const SEQUENCE_LOOKAHEAD: u32 = 5;
+// Password the subscriber states are exported with, to be imported as copies
+const COPY_PASSWORD: &str = "integrity-check";
+
+// A problem found while checking a channel
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Issue {
+    // A message linked to a message that isn't on the transport
+    BrokenChain { link: Address, prev_link: Address },
+    // A message linked to a message that exists but that the reader couldn't read
+    UnreadablePrevious { link: Address, prev_link: Address },
+    // Sequence numbers of a single depth anchor with no message, although later ones have one
+    SequenceSkip { anchor: Address, missing: Vec<u32> },
+    // A sequence message on the transport whose message the reader got nothing out of, because
+    // it is missing from the transport or the reader can't read it
+    SkippedMessage { link: Address },
+    // A keyload that none of the current subscribers can open
+    UnopenableKeyload { link: Address },
+    // The transport failed to tell whether the message at `link` exists
+    TransportError { link: Address, error: String },
+}
+
+impl fmt::Display for Issue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Issue::BrokenChain { link, prev_link } => {
+                write!(f, "{} links to {}, which is not on the transport", link, prev_link)
+            }
+            Issue::UnreadablePrevious { link, prev_link } => {
+                write!(f, "{} links to {}, which the reader can't read", link, prev_link)
+            }
+            Issue::SequenceSkip { anchor, missing } => {
+                write!(f, "Anchor {} skips sequence numbers {:?}", anchor, missing)
+            }
+            Issue::SkippedMessage { link } => {
+                write!(f, "Sequence message {} points to a message the reader didn't read", link)
+            }
+            Issue::UnopenableKeyload { link } => {
+                write!(f, "Keyload {} can't be opened by any current subscriber", link)
+            }
+            Issue::TransportError { link, error } => {
+                write!(f, "Transport failed to check {}: {}", link, error)
+            }
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct IntegrityReport {
+    // Number of messages the reader walked
+    pub checked: usize,
+    pub issues: Vec<Issue>,
+}
+
+impl IntegrityReport {
+    pub fn is_clean(&self) -> bool {
+        self.issues.is_empty()
+    }
+}
+
+impl fmt::Display for IntegrityReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Checked {} msgs, found {} issues", self.checked, self.issues.len())?;
+        for issue in &self.issues {
+            writeln!(f, "\t{}", issue)?;
+        }
+        Ok(())
+    }
+}
+
+/**
+ * Check the integrity of a channel as seen by a reader that has received its announcement,
+ * typically an auditor included in every keyload. The reader walks every branch it has access to,
+ * a round of `fetch_next_msgs` at a time, and what it reads is checked against the raw messages on
+ * the transport:
+ * - The previous message of each message read must exist on the transport, and must have been read
+ *   by the reader
+ * - Each sequence message read must lead to a message read in the same round. Sequence messages
+ *   are consumed while fetching, so they are found by the links polled in a round that have moved
+ *   on by its end, and matched to the signed packets of their publisher, or else to the keyloads
+ *   and tagged packets read, which carry no publisher. A sequence message left over points to a
+ *   message missing from the transport or unreadable to the reader, even one the reader moved past
+ * - Keyloads must be able to be opened by at least one of `subscribers`, the current subscriber set
+ *
+ * Keyloads are opened by copies of the subscribers imported from their exported states, so the
+ * subscribers themselves are left as they were. Transport errors other than a message not being
+ * found are reported as such, rather than as missing messages.
+ *
+ * Sequence numbers of a single depth anchor are only addressable through the anchor, so skips are
+ * also checked on the given anchors, probing until a few numbers in a row have no message.
+ */
+pub async fn check_integrity<R: ChannelReader, T: Transport>(
+    announcement_link: &Address,
+    reader: &mut R,
+    transport: &mut T,
+    subscribers: &[Subscriber<T>],
+    anchors: &[Address],
+) -> Result<IntegrityReport> {
+    let mut report = IntegrityReport::default();
+    let mut msgs = Vec::new();
+    loop {
+        let polled = reader.next_publisher_links();
+        let round = reader.fetch_next_msgs().await;
+        let unread = reader.next_msg_links();
+        let read: Vec<&(String, Address)> =
+            polled.iter().filter(|(_, link)| !unread.contains(link)).collect();
+        // A round can read only sequence messages whose messages the reader can't read, so the
+        // walk goes on until no cursor moves
+        if round.is_empty() && read.is_empty() {
+            break;
+        }
+        for link in unmatched_sequences(&read, &round) {
+            report.issues.push(Issue::SkippedMessage { link });
+        }
+        msgs.extend(round);
+    }
+    report.checked = msgs.len();
+
+    let mut subscriber_states = Vec::new();
+    for subscriber in subscribers {
+        subscriber_states.push(subscriber.export(COPY_PASSWORD).await?);
+    }
+
+    let mut read = HashSet::new();
+    read.insert(announcement_link.clone());
+    read.extend(msgs.iter().map(|msg| msg.link.clone()));
+
+    for msg in &msgs {
+        if !read.contains(&msg.prev_link) {
+            let link = msg.link.clone();
+            let prev_link = msg.prev_link.clone();
+            match transport.recv_messages(&prev_link).await {
+                Ok(found) if !found.is_empty() => {
+                    report.issues.push(Issue::UnreadablePrevious { link, prev_link })
+                }
+                Ok(_) => report.issues.push(Issue::BrokenChain { link, prev_link }),
+                Err(e) if is_not_found(&e) => {
+                    report.issues.push(Issue::BrokenChain { link, prev_link })
+                }
+                Err(e) => report.issues.push(Issue::TransportError {
+                    link: prev_link,
+                    error: e.to_string(),
+                }),
+            }
+        }
+
+        if let MessageContent::Keyload = &msg.body {
+            if !opens_keyload(&subscriber_states, transport, &msg.link).await? {
+                report.issues.push(Issue::UnopenableKeyload { link: msg.link.clone() });
+            }
+        }
+    }
+
+    for anchor in anchors {
+        let missing = sequence_skips(reader, anchor).await;
+        if !missing.is_empty() {
+            report.issues.push(Issue::SequenceSkip { anchor: anchor.clone(), missing });
+        }
+    }
+    Ok(report)
+}
+
+// The links of the sequence messages read in a round that no message read in the round is left
+// for. Links read that are messages themselves, as in single branch channels, are not sequences.
+fn unmatched_sequences(read: &[&(String, Address)], round: &[UnwrappedMessage]) -> Vec<Address> {
+    let mut without_publisher = round.iter().filter(|msg| publisher_key(msg).is_none()).count();
+    let mut unmatched = Vec::new();
+    for (publisher, link) in read {
+        let sent = |msg: &UnwrappedMessage| {
+            msg.link == *link || publisher_key(msg).as_ref() == Some(publisher)
+        };
+        if round.iter().any(sent) {
+            continue;
+        }
+        if without_publisher > 0 {
+            without_publisher -= 1;
+        } else {
+            unmatched.push(link.clone());
+        }
+    }
+    unmatched
+}
+
+fn publisher_key(msg: &UnwrappedMessage) -> Option<String> {
+    match &msg.body {
+        MessageContent::SignedPacket { pk, .. } => Some(hex::encode(pk.as_bytes())),
+        _ => None,
+    }
+}
+
+// Whether a fresh copy of any of the subscribers can open the keyload at `link`
+async fn opens_keyload<T: Transport>(
+    subscriber_states: &[Vec<u8>],
+    transport: &T,
+    link: &Address,
+) -> Result<bool> {
+    for state in subscriber_states {
+        let mut copy = Subscriber::import(state, COPY_PASSWORD, transport.clone()).await?;
+        if copy.receive_msg(link).await.is_ok() {
+            return Ok(true);
+        }
+    }
+    Ok(false)
+}
+
+// Sequence numbers of an anchor with no message, below the last one that has a message
+async fn sequence_skips<R: ChannelReader>(reader: &mut R, anchor: &Address) -> Vec<u32> {
+    let mut gap = Vec::new();
+    let mut skipped = Vec::new();
+    let mut msg_num = 0;
+    while gap.len() < SEQUENCE_LOOKAHEAD as usize {
+        match reader.receive_msg_by_sequence_number(anchor, msg_num).await {
+            Ok(_) => skipped.append(&mut gap),
+            Err(_) => gap.push(msg_num),
+        }
+        msg_num += 1;
+    }
+    skipped
+}
@@ -0,0 +1,205 @@
+pub mod archive;
+pub mod backfill;
+pub mod batch;
+pub mod bridge;
+pub mod catch_up;
+pub mod chunking;
+pub mod compression;
+pub mod history;
+pub mod index;
+pub mod integrity;
+pub mod outbox;
+pub mod range;
+pub mod rotation;
+pub mod scenario;
+pub mod transfer;
+pub mod visualizer;
+
+pub use archive::ChannelArchive;
+pub use backfill::{backfill, Backfill, Provenance};
+pub use batch::{publish_batch, BatchError};
+pub use bridge::Bridge;
+pub use catch_up::{catch_up, CaughtUp};
+pub use chunking::{publish_chunked, receive_chunked, Manifest};
+pub use compression::{Codec, CompressingWriter, DecompressingReader};
+pub use history::{BackwardPages, ForwardPages};
+pub use index::{IndexEntry, MessageIndex};
+pub use integrity::{check_integrity, IntegrityReport, Issue};
+pub use outbox::Outbox;
+pub use range::{receive_range, SequenceRange};
+pub use rotation::RotatingBranch;
+pub use scenario::{Scenario, ScenarioReport};
+pub use transfer::{FileOffer, FileReceiver, FileSender, Progress};
+pub use visualizer::ChannelGraph;
+
+use anyhow::{anyhow, Result};
+use async_trait::async_trait;
+use std::{fs, path::Path};
+use iota_streams::app_channels::api::tangle::{
+    Address, Author, Bytes, Subscriber, Transport, UnwrappedMessage,
+};
+
+// Common read access to a channel, whether walking it as the Author or as a Subscriber
+#[async_trait(?Send)]
+pub trait ChannelReader {
+    async fn fetch_next_msgs(&mut self) -> Vec<UnwrappedMessage>;
+    async fn fetch_all_next_msgs(&mut self) -> Vec<UnwrappedMessage>;
+    // The links that will be polled by the next call to `fetch_next_msgs`. In multi branch
+    // channels these are the sequencing messages of each known publisher.
+    fn next_msg_links(&mut self) -> Vec<Address> {
+        self.next_publisher_links().into_iter().map(|(_publisher, link)| link).collect()
+    }
+    // The links of `next_msg_links`, each with the hex public key of the publisher it is polled for
+    fn next_publisher_links(&mut self) -> Vec<(String, Address)>;
+    // The message linked to by the message at `link`
+    async fn fetch_prev_msg(&mut self, link: &Address) -> Result<UnwrappedMessage>;
+    // The message at `link`, received directly
+    async fn receive_msg(&mut self, link: &Address) -> Result<UnwrappedMessage>;
+    // The message with the given sequence number attached to `anchor` in single depth channels
+    async fn receive_msg_by_sequence_number(
+        &mut self,
+        anchor: &Address,
+        msg_num: u32,
+    ) -> Result<UnwrappedMessage>;
+}
+
+macro_rules! impl_channel_reader {
+    ($user:ident) => {
+        #[async_trait(?Send)]
+        impl<T: Transport> ChannelReader for $user<T> {
+            async fn fetch_next_msgs(&mut self) -> Vec<UnwrappedMessage> {
+                $user::fetch_next_msgs(self).await
+            }
+
+            async fn fetch_all_next_msgs(&mut self) -> Vec<UnwrappedMessage> {
+                $user::fetch_all_next_msgs(self).await
+            }
+
+            fn next_publisher_links(&mut self) -> Vec<(String, Address)> {
+                let branching = self.is_multi_branching();
+                self.gen_next_msg_ids(branching)
+                    .into_iter()
+                    .map(|(pk, cursor)| (hex::encode(pk.as_bytes()), cursor.link))
+                    .collect()
+            }
+
+            async fn fetch_prev_msg(&mut self, link: &Address) -> Result<UnwrappedMessage> {
+                $user::fetch_prev_msg(self, link).await
+            }
+
+            async fn receive_msg(&mut self, link: &Address) -> Result<UnwrappedMessage> {
+                $user::receive_msg(self, link).await
+            }
+
+            async fn receive_msg_by_sequence_number(
+                &mut self,
+                anchor: &Address,
+                msg_num: u32,
+            ) -> Result<UnwrappedMessage> {
+                $user::receive_msg_by_sequence_number(self, anchor, msg_num).await
+            }
+        }
+    };
+}
+
+impl_channel_reader!(Author);
+impl_channel_reader!(Subscriber);
+
+// Common write access to a channel, for publishing as either the Author or a Subscriber
+#[async_trait(?Send)]
+pub trait ChannelWriter {
+    async fn send_signed_packet(
+        &mut self,
+        link_to: &Address,
+        public_payload: &Bytes,
+        masked_payload: &Bytes,
+    ) -> Result<(Address, Option<Address>)>;
+    async fn send_tagged_packet(
+        &mut self,
+        link_to: &Address,
+        public_payload: &Bytes,
+        masked_payload: &Bytes,
+    ) -> Result<(Address, Option<Address>)>;
+    // The publishing user's state, exported with the given password
+    async fn export(&self, password: &str) -> Result<Vec<u8>>;
+    // The link the publishing user's next message is published at
+    fn next_msg_link(&mut self) -> Result<Address>;
+}
+
+macro_rules! impl_channel_writer {
+    ($user:ident) => {
+        #[async_trait(?Send)]
+        impl<T: Transport> ChannelWriter for $user<T> {
+            async fn send_signed_packet(
+                &mut self,
+                link_to: &Address,
+                public_payload: &Bytes,
+                masked_payload: &Bytes,
+            ) -> Result<(Address, Option<Address>)> {
+                $user::send_signed_packet(self, link_to, public_payload, masked_payload).await
+            }
+
+            async fn send_tagged_packet(
+                &mut self,
+                link_to: &Address,
+                public_payload: &Bytes,
+                masked_payload: &Bytes,
+            ) -> Result<(Address, Option<Address>)> {
+                $user::send_tagged_packet(self, link_to, public_payload, masked_payload).await
+            }
+
+            async fn export(&self, password: &str) -> Result<Vec<u8>> {
+                $user::export(self, password).await
+            }
+
+            fn next_msg_link(&mut self) -> Result<Address> {
+                let branching = self.is_multi_branching();
+                let own_pk = *self.get_public_key();
+                self.gen_next_msg_ids(branching)
+                    .into_iter()
+                    .find(|(pk, _cursor)| *pk == own_pk)
+                    .map(|(_pk, cursor)| cursor.link)
+                    .ok_or_else(|| anyhow!("The publishing user has no cursor of its own"))
+            }
+        }
+    };
+}
+
+impl_channel_writer!(Author);
+impl_channel_writer!(Subscriber);
+
+/**
+ * Publish each input as a tagged packet with a masked payload, chaining every packet to the one
+ * before it starting from `link_to`. Tagged packets carry no signature, making them cheaper to
+ * produce and verify than signed packets, at the cost of readers not being able to tell which
+ * publisher sent them. Returns the links of the sent packets along with the last sequencing link
+ * in multi branch channels.
+ */
+pub async fn send_tagged_packets<W: ChannelWriter>(
+    writer: &mut W,
+    link_to: &Address,
+    inputs: &[&str],
+) -> Result<(Vec<Address>, Option<Address>)> {
+    let mut links = Vec::new();
+    let mut last_seq_link = None;
+    let mut prev_msg_link = link_to.clone();
+    for input in inputs {
+        let (msg_link, seq_link) = writer.send_tagged_packet(
+            &prev_msg_link,
+            &Bytes::default(),
+            &Bytes(input.as_bytes().to_vec()),
+        ).await?;
+        links.push(msg_link.clone());
+        last_seq_link = seq_link.or(last_seq_link);
+        prev_msg_link = msg_link;
+    }
+    Ok((links, last_seq_link))
+}
+
+// Write a file through a temporary file first, so that a crash never leaves a partial file behind
+pub(crate) fn write_atomic<C: AsRef<[u8]>>(path: &Path, contents: C) -> Result<()> {
+    let tmp_path = path.with_extension("tmp");
+    fs::write(&tmp_path, contents)?;
+    fs::rename(&tmp_path, path)?;
+    Ok(())
+}
@@ -0,0 +1,260 @@
+use anyhow::Result;
+use iota_streams::app_channels::api::tangle::{Address, Bytes};
+use core::str::FromStr;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::VecDeque,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use crate::tools::{write_atomic, ChannelReader, ChannelWriter};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PacketKind {
+    Signed,
+    Tagged,
+}
+
+// A payload waiting to be published, base64 encoded
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct PendingPacket {
+    pub kind: PacketKind,
+    pub public_payload: String,
+    pub masked_payload: String,
+}
+
+// The persisted contents of an outbox
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+struct OutboxFile {
+    // The tip of the branch the next packet is attached to
+    prev_link: String,
+    pending: VecDeque<PendingPacket>,
+    // Exported state of the publishing user as of the last packet sent, base64 encoded
+    #[serde(default)]
+    writer_state: String,
+    // The link the packet at the front of the queue is going to be published at, recorded before
+    // it is sent
+    #[serde(default)]
+    in_flight: Option<String>,
+}
+
+// The packets sent by a flush, and the error that stopped it with packets still queued
+#[derive(Debug, Default)]
+pub struct Flushed {
+    pub sent: Vec<Address>,
+    pub stopped_by: Option<anyhow::Error>,
+}
+
+/**
+ * A durable queue of packets to publish, for publishers that lose connectivity. Payloads are
+ * written to disk before any attempt is made to send them, and are published strictly in order,
+ * each linked to the packet sent before it. A packet is only removed from the outbox once it has
+ * been sent, at which point the branch tip is moved on to it, so an outbox reopened after a
+ * restart carries on linking from the right place.
+ *
+ * After every packet sent, the exported state of the publishing user is persisted along with the
+ * outbox in a single write, so that its sequencing state always matches the stored tip. A restarted
+ * publisher is restored from `writer_state`. The link each packet is going to be published at is
+ * recorded before it is sent, so a packet sent just before a crash is found at that link by the
+ * next flush and taken off the queue instead of being published twice.
+ */
+#[derive(Debug)]
+pub struct Outbox {
+    path: PathBuf,
+    file: OutboxFile,
+}
+
+impl Outbox {
+    // Open the outbox stored at `path`, or create one attaching its first packet to `link_to`
+    pub fn open<P: AsRef<Path>>(path: P, link_to: &Address) -> Result<Self> {
+        let path = path.as_ref().to_path_buf();
+        if path.exists() {
+            let file = serde_json::from_str(&fs::read_to_string(&path)?)?;
+            return Ok(Outbox { path, file });
+        }
+        let outbox = Outbox {
+            path,
+            file: OutboxFile {
+                prev_link: link_to.to_string(),
+                pending: VecDeque::new(),
+                writer_state: String::new(),
+                in_flight: None,
+            },
+        };
+        outbox.save()?;
+        Ok(outbox)
+    }
+
+    pub fn prev_link(&self) -> Result<Address> {
+        Ok(Address::from_str(&self.file.prev_link)?)
+    }
+
+    // The exported state of the publishing user as of the last packet sent, if any were sent
+    pub fn writer_state(&self) -> Result<Option<Vec<u8>>> {
+        if self.file.writer_state.is_empty() {
+            return Ok(None);
+        }
+        Ok(Some(base64::decode(&self.file.writer_state)?))
+    }
+
+    pub fn pending(&self) -> usize {
+        self.file.pending.len()
+    }
+
+    pub fn enqueue(
+        &mut self,
+        kind: PacketKind,
+        public_payload: &Bytes,
+        masked_payload: &Bytes,
+    ) -> Result<()> {
+        self.file.pending.push_back(PendingPacket {
+            kind,
+            public_payload: base64::encode(&public_payload.0),
+            masked_payload: base64::encode(&masked_payload.0),
+        });
+        self.save()
+    }
+
+    /**
+     * Send pending packets in order until the outbox is empty or a send fails, returning the links
+     * of the packets sent along with the error that stopped the flush. A packet that couldn't be
+     * sent stays queued for the next flush, whatever the error, since the node not being reachable
+     * can't be told apart from any other failure. Errors persisting the outbox are returned.
+     */
+    pub async fn flush<W: ChannelWriter + ChannelReader>(
+        &mut self,
+        writer: &mut W,
+        password: &str,
+    ) -> Result<Flushed> {
+        let mut flushed = Flushed::default();
+        if let Some(msg_link) = self.settle_in_flight(writer, password).await? {
+            flushed.sent.push(msg_link);
+        }
+        while let Some(packet) = self.file.pending.front() {
+            let prev_link = self.prev_link()?;
+            let public_payload = Bytes(base64::decode(&packet.public_payload)?);
+            let masked_payload = Bytes(base64::decode(&packet.masked_payload)?);
+            let kind = packet.kind;
+            self.file.in_flight = Some(writer.next_msg_link()?.to_string());
+            self.save()?;
+            let result = match kind {
+                PacketKind::Signed => {
+                    writer.send_signed_packet(&prev_link, &public_payload, &masked_payload).await
+                }
+                PacketKind::Tagged => {
+                    writer.send_tagged_packet(&prev_link, &public_payload, &masked_payload).await
+                }
+            };
+            match result {
+                Ok((msg_link, _seq)) => {
+                    self.sent(writer, &msg_link, password).await?;
+                    flushed.sent.push(msg_link);
+                }
+                Err(e) => {
+                    flushed.stopped_by = Some(e);
+                    break;
+                }
+            }
+        }
+        Ok(flushed)
+    }
+
+    // Check whether the packet being sent when the outbox was last saved made it to its link. If
+    // it did, the publishing user catches up with it and it is taken off the queue, otherwise it is
+    // sent again, to the same link, by the rest of the flush.
+    async fn settle_in_flight<W: ChannelWriter + ChannelReader>(
+        &mut self,
+        writer: &mut W,
+        password: &str,
+    ) -> Result<Option<Address>> {
+        let msg_link = match &self.file.in_flight {
+            Some(link) => Address::from_str(link)?,
+            None => return Ok(None),
+        };
+        match writer.receive_msg(&msg_link).await {
+            Ok(_msg) => {
+                self.sent(writer, &msg_link, password).await?;
+                Ok(Some(msg_link))
+            }
+            Err(_) => Ok(None),
+        }
+    }
+
+    // Take the packet at the front of the queue off it, now that it is published at `msg_link`
+    async fn sent<W: ChannelWriter>(
+        &mut self,
+        writer: &mut W,
+        msg_link: &Address,
+        password: &str,
+    ) -> Result<()> {
+        self.file.pending.pop_front();
+        self.file.in_flight = None;
+        self.file.prev_link = msg_link.to_string();
+        self.file.writer_state = base64::encode(writer.export(password).await?);
+        self.save()
+    }
+
+    // Queue a packet and try to send everything that is pending
+    pub async fn publish<W: ChannelWriter + ChannelReader>(
+        &mut self,
+        writer: &mut W,
+        kind: PacketKind,
+        public_payload: &Bytes,
+        masked_payload: &Bytes,
+        password: &str,
+    ) -> Result<Flushed> {
+        self.enqueue(kind, public_payload, masked_payload)?;
+        self.flush(writer, password).await
+    }
+
+    fn save(&self) -> Result<()> {
+        write_atomic(&self.path, serde_json::to_string_pretty(&self.file)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::transport::MemoryTransport;
+    use futures::executor::block_on;
+    use iota_streams::app_channels::api::tangle::{Author, ChannelType, Subscriber};
+
+    #[test]
+    fn packet_sent_before_a_crash_is_not_sent_again() {
+        block_on(async {
+            let transport = MemoryTransport::new();
+            let mut author =
+                Author::new("OutboxAuthor", ChannelType::SingleBranch, transport.clone());
+            let announcement_link = author.send_announce().await?;
+            let name = format!("outbox-{}.json", hex::encode(announcement_link.to_msg_index()));
+            let path = std::env::temp_dir().join(name);
+            let mut outbox = Outbox::open(&path, &announcement_link)?;
+            outbox.enqueue(PacketKind::Signed, &Bytes::default(), &Bytes(b"Once".to_vec()))?;
+            let state = author.export("password").await?;
+
+            // The packet is recorded as in flight and sent, but the device stops before the outbox
+            // is saved again
+            outbox.file.in_flight = Some(author.next_msg_link()?.to_string());
+            outbox.save()?;
+            author
+                .send_signed_packet(&announcement_link, &Bytes::default(), &Bytes(b"Once".to_vec()))
+                .await?;
+
+            let mut outbox = Outbox::open(&path, &announcement_link)?;
+            let mut author = Author::import(&state, "password", transport.clone()).await?;
+            let flushed = outbox.flush(&mut author, "password").await?;
+            fs::remove_file(&path)?;
+            assert_eq!(flushed.sent.len(), 1);
+            assert!(flushed.stopped_by.is_none());
+            assert_eq!(outbox.pending(), 0);
+
+            let mut subscriber = Subscriber::new("OutboxReader", transport);
+            subscriber.receive_announcement(&announcement_link).await?;
+            assert_eq!(subscriber.fetch_all_next_msgs().await.len(), 1);
+            Ok::<(), anyhow::Error>(())
+        })
+        .unwrap();
+    }
+}
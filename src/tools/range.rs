@@ -0,0 +1,87 @@
+use iota_streams::app_channels::api::tangle::{Address, Transport, UnwrappedMessage};
+use std::{collections::HashMap, ops::Range};
+
+use crate::{
+    tools::ChannelReader,
+    transport::{is_not_found, PrefetchTransport},
+};
+
+// The outcome of reading a range of sequence numbers from a single depth anchor
+#[derive(Default)]
+pub struct SequenceRange {
+    // Messages that were found and read, by sequence number in ascending order
+    pub messages: Vec<(u32, UnwrappedMessage)>,
+    // Sequence numbers with no message published at them
+    pub missing: Vec<u32>,
+    // Sequence numbers with a message the reader could not read
+    pub unreadable: Vec<u32>,
+    // Sequence numbers that could not be fetched from the transport, and why
+    pub failed: Vec<(u32, anyhow::Error)>,
+}
+
+impl SequenceRange {
+    pub fn is_complete(&self) -> bool {
+        self.missing.is_empty() && self.unreadable.is_empty() && self.failed.is_empty()
+    }
+}
+
+/**
+ * Read the messages with sequence numbers `[range.start, range.end)` attached to `anchor` in a
+ * single depth channel, with at most `concurrency` requests in flight. The reader must be reading
+ * over `transport`.
+ *
+ * The link of each sequence number is worked out by the reader, so the transport first records
+ * the links the reader asks for, then prefetches them all concurrently, and finally the reader reads
+ * each message from the prefetched ones. Only the reader's own requests are recorded, so the
+ * transport can be shared with other users. Gaps in the range don't stop the read: sequence numbers
+ * without a message are reported as missing, while those the transport failed to fetch are reported
+ * separately with their errors, since a message may well be published at them.
+ */
+pub async fn receive_range<R: ChannelReader, T: Transport>(
+    reader: &mut R,
+    transport: &PrefetchTransport<T>,
+    anchor: &Address,
+    range: Range<u32>,
+    concurrency: usize,
+) -> SequenceRange {
+    let ((), recorded) = transport
+        .recording(async {
+            for msg_num in range.clone() {
+                // Fails without a round trip, noting the link of the message
+                let _ = reader.receive_msg_by_sequence_number(anchor, msg_num).await;
+            }
+        })
+        .await;
+    // Each read asks for exactly one link, otherwise the messages are read one at a time
+    let mut failures = HashMap::new();
+    let links: Vec<Option<Address>> = if recorded.len() == range.len() {
+        let prefetched = transport.prefetch(recorded.clone(), concurrency).await;
+        failures.extend(prefetched.failed);
+        recorded.into_iter().map(Some).collect()
+    } else {
+        vec![None; range.len()]
+    };
+
+    let mut result = SequenceRange::default();
+    for (msg_num, link) in range.zip(links) {
+        if let Some(e) = link.as_ref().and_then(|link| failures.remove(link)) {
+            result.failed.push((msg_num, e));
+            continue;
+        }
+        let found = link.map(|link| transport.contains(&link));
+        if found == Some(false) {
+            result.missing.push(msg_num);
+            continue;
+        }
+        match reader.receive_msg_by_sequence_number(anchor, msg_num).await {
+            Ok(msg) => result.messages.push((msg_num, msg)),
+            // Read one at a time, so the error tells a gap apart from a failed request
+            Err(e) if found.is_none() && is_not_found(&e) => result.missing.push(msg_num),
+            Err(e) if found.is_none() => result.failed.push((msg_num, e)),
+            Err(_) => result.unreadable.push(msg_num),
+        }
+    }
+    // Messages that couldn't be read are left in the cache
+    transport.evict();
+    result
+}
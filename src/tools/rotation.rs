@@ -0,0 +1,164 @@
+use anyhow::{ensure, Result};
+use iota_streams::app_channels::api::tangle::{Address, Author, Bytes, PublicKey, Transport};
+use std::time::{Duration, Instant};
+
+// When a branch is due for a fresh keyload. A branch is rotated as soon as either limit is reached.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct RotationPolicy {
+    pub max_messages: Option<usize>,
+    pub max_age: Option<Duration>,
+}
+
+impl RotationPolicy {
+    pub fn every_messages(max_messages: usize) -> Self {
+        RotationPolicy { max_messages: Some(max_messages), ..Default::default() }
+    }
+
+    pub fn every_duration(max_age: Duration) -> Self {
+        RotationPolicy { max_age: Some(max_age), ..Default::default() }
+    }
+}
+
+// The users included in the keyloads sent by the branch tools
+#[derive(Clone, Debug)]
+pub enum Membership {
+    // Every subscriber and PSK known to the Author at the time of the rotation
+    Everyone,
+    // A fixed set of subscriber public keys
+    Subscribers(Vec<[u8; 32]>),
+}
+
+/**
+ * A private branch that is periodically re-keyed. Every rotation issues a fresh keyload for the
+ * same membership attached to the current sequence link of the branch (or the last message in
+ * single branch channels), so that the session key in use changes while subscribers following the
+ * channel with `fetch_next_msgs` move onto the new keyload without any changes on their side.
+ *
+ * Rotation is lazy: nothing runs in the background, so a branch past its `max_age` keeps its keys
+ * until the next publish, which rotates first whenever the policy says it is due. Applications that
+ * need the keys replaced on time even while idle should call `rotate_if_due` from their own timer.
+ */
+#[derive(Clone, Debug)]
+pub struct RotatingBranch {
+    policy: RotationPolicy,
+    membership: Membership,
+    keyload_link: Address,
+    prev_msg_link: Address,
+    tip: Address,
+    sent_since_rotation: usize,
+    rotated_at: Instant,
+    rotations: usize,
+}
+
+impl RotatingBranch {
+    // Open the branch with its first keyload attached to `link_to`
+    pub async fn open<T: Transport>(
+        author: &mut Author<T>,
+        link_to: &Address,
+        membership: Membership,
+        policy: RotationPolicy,
+    ) -> Result<Self> {
+        ensure!(
+            policy.max_messages != Some(0),
+            "A rotation policy needs to allow at least one message per keyload"
+        );
+        let (keyload_link, seq_link) = send_keyload(author, link_to, &membership).await?;
+        Ok(RotatingBranch {
+            policy,
+            membership,
+            tip: seq_link.unwrap_or_else(|| keyload_link.clone()),
+            prev_msg_link: keyload_link.clone(),
+            keyload_link,
+            sent_since_rotation: 0,
+            rotated_at: Instant::now(),
+            rotations: 0,
+        })
+    }
+
+    pub fn keyload_link(&self) -> &Address {
+        &self.keyload_link
+    }
+
+    pub fn rotations(&self) -> usize {
+        self.rotations
+    }
+
+    pub fn membership(&self) -> &Membership {
+        &self.membership
+    }
+
+    // Change who is included from the next rotation on
+    pub fn set_membership(&mut self, membership: Membership) {
+        self.membership = membership;
+    }
+
+    pub fn is_due(&self) -> bool {
+        let count_due = self
+            .policy
+            .max_messages
+            .map_or(false, |max| self.sent_since_rotation >= max);
+        let age_due = self
+            .policy
+            .max_age
+            .map_or(false, |max| self.sent_since_rotation > 0 && self.rotated_at.elapsed() >= max);
+        count_due || age_due
+    }
+
+    // Issue a fresh keyload for the membership on the current tip of the branch
+    pub async fn rotate<T: Transport>(&mut self, author: &mut Author<T>) -> Result<Address> {
+        let (keyload_link, seq_link) = send_keyload(author, &self.tip, &self.membership).await?;
+        self.rotations += 1;
+        self.tip = seq_link.unwrap_or_else(|| keyload_link.clone());
+        self.prev_msg_link = keyload_link.clone();
+        self.keyload_link = keyload_link.clone();
+        self.sent_since_rotation = 0;
+        self.rotated_at = Instant::now();
+        Ok(keyload_link)
+    }
+
+    // Rotate the branch if the policy says it is due, returning the new keyload link if it was
+    pub async fn rotate_if_due<T: Transport>(
+        &mut self,
+        author: &mut Author<T>,
+    ) -> Result<Option<Address>> {
+        if !self.is_due() {
+            return Ok(None);
+        }
+        self.rotate(author).await.map(Some)
+    }
+
+    // Publish a signed packet to the branch, rotating the keys beforehand if due
+    pub async fn publish<T: Transport>(
+        &mut self,
+        author: &mut Author<T>,
+        public_payload: &Bytes,
+        masked_payload: &Bytes,
+    ) -> Result<Address> {
+        self.rotate_if_due(author).await?;
+        let (msg_link, seq_link) = author
+            .send_signed_packet(&self.prev_msg_link, public_payload, masked_payload)
+            .await?;
+        self.tip = seq_link.unwrap_or_else(|| msg_link.clone());
+        self.prev_msg_link = msg_link.clone();
+        self.sent_since_rotation += 1;
+        Ok(msg_link)
+    }
+}
+
+// Send a keyload including exactly the given membership
+pub(crate) async fn send_keyload<T: Transport>(
+    author: &mut Author<T>,
+    link_to: &Address,
+    membership: &Membership,
+) -> Result<(Address, Option<Address>)> {
+    match membership {
+        Membership::Everyone => author.send_keyload_for_everyone(link_to).await,
+        Membership::Subscribers(pks) => {
+            let keys = pks
+                .iter()
+                .map(|pk| Ok(PublicKey::from_bytes(pk)?.into()))
+                .collect::<Result<Vec<_>>>()?;
+            author.send_keyload(link_to, &keys).await
+        }
+    }
+}
@@ -0,0 +1,517 @@
+use anyhow::{anyhow, bail, ensure, Result};
+use iota_streams::app_channels::api::{
+    psk_from_seed,
+    pskid_from_psk,
+    tangle::{
+        Address, Author, Bytes, ChannelType, MessageContent, PublicKey, Subscriber, Transport,
+        UnwrappedMessage,
+    },
+};
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+
+use crate::examples::generate_seed;
+
+pub const AUTHOR: &str = "Author";
+pub const EVERYONE: &str = "everyone";
+
+#[derive(Clone, Copy, Debug, Deserialize, Serialize)]
+pub enum ScenarioChannelType {
+    SingleBranch,
+    MultiBranch,
+    SingleDepth,
+}
+
+impl From<ScenarioChannelType> for ChannelType {
+    fn from(channel_type: ScenarioChannelType) -> Self {
+        match channel_type {
+            ScenarioChannelType::SingleBranch => ChannelType::SingleBranch,
+            ScenarioChannelType::MultiBranch => ChannelType::MultiBranch,
+            ScenarioChannelType::SingleDepth => ChannelType::SingleDepth,
+        }
+    }
+}
+
+// How a subscriber is given access to the channel
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Access {
+    // Sends a subscription message that the Author processes
+    #[default]
+    Subscribe,
+    // Shares a Pre Shared Key with the Author
+    Psk,
+    // Only receives the announcement
+    None,
+}
+
+// Where a branch is attached
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LinkTo {
+    #[default]
+    Announcement,
+    // The tip of the previous branch. In multi branch channels this is the last sequencing message,
+    // allowing new readers to access the branch without access to the previous one.
+    Previous,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct SubscriberSpec {
+    pub name: String,
+    #[serde(default)]
+    pub access: Access,
+    // The branch this subscriber joins right before. Subscribers join at the start by default.
+    #[serde(default)]
+    pub join_before: Option<String>,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct BranchSpec {
+    pub name: String,
+    // Subscribers granted access to the branch (or `everyone`). Public branches have no keyload.
+    #[serde(default)]
+    pub keyload: Option<Vec<String>>,
+    #[serde(default)]
+    pub link_to: LinkTo,
+    // Messages are published by each publisher in turn
+    #[serde(default = "default_publishers")]
+    pub publishers: Vec<String>,
+    pub messages: Vec<String>,
+}
+
+fn default_publishers() -> Vec<String> {
+    vec![AUTHOR.to_string()]
+}
+
+/**
+ * A channel workflow described as data: the identities taking part, the channel type, the
+ * keyloads that create each branch, who publishes what in each branch, and which branches every
+ * identity is expected to be able to read. Running a scenario executes the workflow against any
+ * transport and checks that each identity reads exactly the expected messages.
+ */
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct Scenario {
+    pub name: String,
+    pub channel_type: ScenarioChannelType,
+    #[serde(default)]
+    pub subscribers: Vec<SubscriberSpec>,
+    pub branches: Vec<BranchSpec>,
+    // Identity name -> names of the branches it should be able to read
+    #[serde(default)]
+    pub expect: BTreeMap<String, Vec<String>>,
+}
+
+// The links published while running a scenario and how many messages each identity read
+#[derive(Clone, Debug)]
+pub struct ScenarioReport {
+    pub announcement_link: Address,
+    // Branch name -> the keyload that created it
+    pub keyloads: BTreeMap<String, Address>,
+    // Branch name, publisher and link of every message, in the order they were sent
+    pub messages: Vec<(String, String, Address)>,
+    // Identity name -> number of expected messages read
+    pub read: BTreeMap<String, usize>,
+}
+
+impl Scenario {
+    pub fn from_yaml(yaml: &str) -> Result<Self> {
+        let scenario: Scenario = serde_yaml::from_str(yaml)?;
+        scenario.validate()?;
+        Ok(scenario)
+    }
+
+    // Check that every name the scenario refers to is declared, and that it can be run as written
+    pub fn validate(&self) -> Result<()> {
+        let mut identities = BTreeSet::new();
+        identities.insert(AUTHOR);
+        for spec in &self.subscribers {
+            ensure!(
+                spec.name != AUTHOR && spec.name != EVERYONE,
+                "Scenario '{}': '{}' is reserved and can't name a subscriber", self.name, spec.name
+            );
+            ensure!(
+                identities.insert(spec.name.as_str()),
+                "Scenario '{}': subscriber '{}' is declared twice", self.name, spec.name
+            );
+            self.join_step(spec)?;
+        }
+
+        let mut branches = BTreeSet::new();
+        for branch in &self.branches {
+            ensure!(
+                branches.insert(branch.name.as_str()),
+                "Scenario '{}': branch '{}' is declared twice", self.name, branch.name
+            );
+            ensure!(
+                !branch.publishers.is_empty(),
+                "Scenario '{}': branch '{}' has no publishers", self.name, branch.name
+            );
+            for publisher in &branch.publishers {
+                ensure!(
+                    identities.contains(publisher.as_str()),
+                    "Scenario '{}': unknown publisher '{}' in branch '{}'",
+                    self.name, publisher, branch.name
+                );
+            }
+            for name in branch.keyload.iter().flatten() {
+                if name != EVERYONE && !self.subscribers.iter().any(|spec| &spec.name == name) {
+                    bail!(
+                        "Scenario '{}': unknown subscriber '{}' in the keyload of branch '{}'",
+                        self.name, name, branch.name
+                    );
+                }
+            }
+        }
+
+        for (name, expected) in &self.expect {
+            ensure!(
+                identities.contains(name.as_str()),
+                "Scenario '{}': unknown identity '{}' in expectations", self.name, name
+            );
+            for branch in expected {
+                ensure!(
+                    branches.contains(branch.as_str()),
+                    "Scenario '{}': unknown branch '{}' expected to be read by {}",
+                    self.name, branch, name
+                );
+            }
+        }
+        Ok(())
+    }
+
+    // The step of the scenario at which a subscriber joins the channel
+    fn join_step(&self, spec: &SubscriberSpec) -> Result<usize> {
+        match &spec.join_before {
+            None => Ok(0),
+            Some(name) => self
+                .branches
+                .iter()
+                .position(|branch| &branch.name == name)
+                .ok_or_else(|| anyhow!("Unknown branch '{}' for {} to join before", name, spec.name)),
+        }
+    }
+
+    pub async fn run<T: Transport>(&self, transport: T) -> Result<ScenarioReport> {
+        self.validate()?;
+        let mut author = Author::new(&generate_seed(), self.channel_type.into(), transport.clone());
+        let announcement_link = author.send_announce().await?;
+        let mut report = ScenarioReport {
+            announcement_link: announcement_link.clone(),
+            keyloads: BTreeMap::new(),
+            messages: Vec::new(),
+            read: BTreeMap::new(),
+        };
+
+        // Messages read by each identity while the scenario runs
+        let mut read: BTreeMap<String, Vec<UnwrappedMessage>> = BTreeMap::new();
+        read.insert(AUTHOR.to_string(), Vec::new());
+        let mut subscribers = BTreeMap::new();
+        let mut psks = BTreeMap::new();
+
+        // The messages published in each branch, along with who published them
+        let mut published: BTreeMap<String, Vec<(String, String)>> = BTreeMap::new();
+        let mut tip = announcement_link.clone();
+
+        for step in 0..=self.branches.len() {
+            // Subscribers join the channel right before the branch they are listed against
+            for spec in &self.subscribers {
+                if self.join_step(spec)? != step {
+                    continue;
+                }
+                let mut subscriber = Subscriber::new(&generate_seed(), transport.clone());
+                subscriber.receive_announcement(&announcement_link).await?;
+                match spec.access {
+                    Access::Subscribe => {
+                        let subscribe_link = subscriber.send_subscribe(&announcement_link).await?;
+                        author.receive_subscribe(&subscribe_link).await?;
+                    }
+                    Access::Psk => {
+                        let key = rand::thread_rng().gen::<[u8; 32]>();
+                        let psk = psk_from_seed(&key);
+                        let pskid = pskid_from_psk(&psk);
+                        author.store_psk(pskid, psk)?;
+                        subscriber.store_psk(pskid, psk)?;
+                        psks.insert(spec.name.clone(), pskid);
+                    }
+                    Access::None => (),
+                }
+                subscribers.insert(spec.name.clone(), subscriber);
+                read.insert(spec.name.clone(), Vec::new());
+            }
+
+            let branch = match self.branches.get(step) {
+                Some(branch) => branch,
+                None => break,
+            };
+            let anchor = match branch.link_to {
+                LinkTo::Announcement => announcement_link.clone(),
+                LinkTo::Previous => tip.clone(),
+            };
+
+            let root = match &branch.keyload {
+                None => anchor,
+                Some(names) => {
+                    let (keyload_link, seq_link) = if names.iter().any(|name| name == EVERYONE) {
+                        author.send_keyload_for_everyone(&anchor).await?
+                    } else {
+                        let mut keys = Vec::new();
+                        for name in names {
+                            if let Some(pskid) = psks.get(name) {
+                                keys.push((*pskid).into());
+                            } else {
+                                let subscriber = subscribers
+                                    .get(name)
+                                    .ok_or_else(|| anyhow!("Unknown subscriber '{}' in keyload", name))?;
+                                keys.push(PublicKey::from_bytes(subscriber.get_public_key().as_bytes())?.into());
+                            }
+                        }
+                        author.send_keyload(&anchor, &keys).await?
+                    };
+                    report.keyloads.insert(branch.name.clone(), keyload_link.clone());
+                    tip = seq_link.unwrap_or_else(|| keyload_link.clone());
+                    keyload_link
+                }
+            };
+
+            let mut prev_msg_link = root.clone();
+            for (i, input) in branch.messages.iter().enumerate() {
+                let publisher = &branch.publishers[i % branch.publishers.len()];
+                let payload = Bytes(input.as_bytes().to_vec());
+
+                // Publishers sync their state before sending. Anything found while syncing counts
+                // towards what they have read.
+                let (msg_link, seq_link) = if publisher == AUTHOR {
+                    let msgs = author.fetch_all_next_msgs().await;
+                    read.get_mut(AUTHOR).unwrap().extend(msgs);
+                    author.send_signed_packet(&prev_msg_link, &Bytes::default(), &payload).await?
+                } else {
+                    let subscriber = subscribers
+                        .get_mut(publisher)
+                        .ok_or_else(|| anyhow!("Unknown publisher '{}'", publisher))?;
+                    let msgs = subscriber.fetch_all_next_msgs().await;
+                    read.get_mut(publisher).unwrap().extend(msgs);
+                    subscriber.send_signed_packet(&prev_msg_link, &Bytes::default(), &payload).await?
+                };
+                report.messages.push((branch.name.clone(), publisher.clone(), msg_link.clone()));
+
+                // Single depth channels anchor every message to the branch root
+                if let ScenarioChannelType::SingleDepth = self.channel_type {
+                    prev_msg_link = root.clone();
+                } else {
+                    prev_msg_link = msg_link.clone();
+                }
+                tip = seq_link.unwrap_or(msg_link);
+                published
+                    .entry(branch.name.clone())
+                    .or_default()
+                    .push((publisher.clone(), input.clone()));
+            }
+        }
+
+        // Catch every identity up and compare what they read against the expectations
+        let msgs = author.fetch_all_next_msgs().await;
+        read.get_mut(AUTHOR).unwrap().extend(msgs);
+        for (name, subscriber) in subscribers.iter_mut() {
+            let msgs = subscriber.fetch_all_next_msgs().await;
+            read.get_mut(name).unwrap().extend(msgs);
+        }
+
+        for (name, branches) in &self.expect {
+            let retrieved = read
+                .get(name)
+                .ok_or_else(|| anyhow!("Unknown identity '{}' in expectations", name))?;
+            // Publishers don't read back their own messages
+            let mut expected = branches
+                .iter()
+                .flat_map(|branch| published.get(branch).cloned().unwrap_or_default())
+                .filter(|(publisher, _)| publisher != name)
+                .map(|(_, msg)| msg)
+                .collect::<Vec<String>>();
+            let mut actual = masked_payloads(retrieved);
+            expected.sort();
+            actual.sort();
+            ensure!(
+                expected == actual,
+                "Scenario '{}': {} expected to read {:?} but read {:?}",
+                self.name, name, expected, actual
+            );
+            report.read.insert(name.clone(), actual.len());
+        }
+
+        Ok(report)
+    }
+}
+
+fn masked_payloads(msgs: &[UnwrappedMessage]) -> Vec<String> {
+    msgs.iter()
+        .filter_map(|msg| match &msg.body {
+            MessageContent::SignedPacket { masked_payload, .. }
+            | MessageContent::TaggedPacket { masked_payload, .. } => {
+                Some(String::from_utf8_lossy(&masked_payload.0).to_string())
+            }
+            _ => None,
+        })
+        .collect()
+}
+
+/**
+ * Property tests of the access rules shown in the examples, over randomly generated multi branch
+ * channels run on an offline transport. Each case is a random sequence of subscribers joining
+ * (traditionally, with a PSK, or not at all), keyloads for random subsets of them, and publishes
+ * by the Author and keyloaded subscribers. For every identity it checks that:
+ * - Public branches are readable by everyone
+ * - Private branches are readable exactly by the subscribers in their keyload, so subscribers
+ *   left out of a later keyload lose forward access
+ * - Subscribers added to a later keyload gain no access to earlier branches
+ *
+ * Failing cases are shrunk and their seeds persisted by proptest under `proptest-regressions`.
+ */
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::transport::MemoryTransport;
+    use futures::executor::block_on;
+    use proptest::{collection::vec, prelude::*};
+
+    const MAX_SUBSCRIBERS: usize = 4;
+    const MAX_BRANCHES: usize = 4;
+
+    // The random choices made for a branch, resolved against the subscribers by `build_scenario`
+    #[derive(Clone, Debug)]
+    struct BranchPlan {
+        public: bool,
+        link_previous: bool,
+        // Whether each subscriber is included in the keyload, and then whether it publishes
+        members: Vec<bool>,
+        publishers: Vec<bool>,
+        message_count: usize,
+    }
+
+    fn access() -> impl Strategy<Value = Access> {
+        prop_oneof![2 => Just(Access::Subscribe), 1 => Just(Access::Psk), 1 => Just(Access::None)]
+    }
+
+    prop_compose! {
+        fn branch_plan()(
+            public in prop::bool::weighted(0.2),
+            link_previous in any::<bool>(),
+            members in vec(any::<bool>(), MAX_SUBSCRIBERS),
+            publishers in vec(any::<bool>(), MAX_SUBSCRIBERS),
+            message_count in 1..4usize,
+        ) -> BranchPlan {
+            BranchPlan { public, link_previous, members, publishers, message_count }
+        }
+    }
+
+    prop_compose! {
+        fn keyload_scenario()(
+            // The access of each subscriber, and the step it joins at
+            subscribers in vec((access(), 0..=MAX_BRANCHES), 1..=MAX_SUBSCRIBERS),
+            branches in vec(branch_plan(), 1..=MAX_BRANCHES),
+        ) -> Scenario {
+            build_scenario(&subscribers, &branches)
+        }
+    }
+
+    // Build a scenario from the random choices, along with the branches each identity should be
+    // able to read under the keyload access rules
+    fn build_scenario(subscribers: &[(Access, usize)], plans: &[BranchPlan]) -> Scenario {
+        // Subscribers join at the start or right before one of the branches
+        let join_steps = subscribers
+            .iter()
+            .map(|(_, step)| (*step).min(plans.len()))
+            .collect::<Vec<usize>>();
+        let subscribers = subscribers
+            .iter()
+            .zip(&join_steps)
+            .enumerate()
+            .map(|(i, ((access, _), step))| SubscriberSpec {
+                name: format!("Subscriber{}", i),
+                access: *access,
+                join_before: step.checked_sub(1).map(|branch| format!("Branch{}", branch)),
+            })
+            .collect::<Vec<SubscriberSpec>>();
+
+        let mut branches = Vec::new();
+        for (i, plan) in plans.iter().enumerate() {
+            // Only subscribers that have joined and have access can be included in a keyload
+            let members = subscribers
+                .iter()
+                .zip(&join_steps)
+                .zip(plan.members.iter().zip(&plan.publishers))
+                .filter(|((spec, step), (member, _))| {
+                    **member && spec.access != Access::None && **step <= i
+                })
+                .map(|((spec, _), (_, publishes))| (spec, *publishes))
+                .collect::<Vec<(&SubscriberSpec, bool)>>();
+
+            let keyload = if members.is_empty() || plan.public {
+                None
+            } else {
+                Some(members.iter().map(|(spec, _)| spec.name.clone()).collect::<Vec<String>>())
+            };
+            // Keyloaded subscribers may publish in the branch, PSK's are for read access only
+            let mut publishers = vec![AUTHOR.to_string()];
+            if keyload.is_some() {
+                publishers.extend(
+                    members
+                        .iter()
+                        .filter(|(spec, publishes)| *publishes && spec.access == Access::Subscribe)
+                        .map(|(spec, _)| spec.name.clone()),
+                );
+            }
+
+            let name = format!("Branch{}", i);
+            let messages = (0..plan.message_count).map(|j| format!("{}-{}", name, j)).collect();
+            branches.push(BranchSpec {
+                name,
+                keyload,
+                link_to: if plan.link_previous { LinkTo::Previous } else { LinkTo::Announcement },
+                publishers,
+                messages,
+            });
+        }
+
+        let mut expect = BTreeMap::new();
+        // The Author reads everything published by others
+        expect.insert(AUTHOR.to_string(), branches.iter().map(|branch| branch.name.clone()).collect());
+        for spec in &subscribers {
+            let readable = branches
+                .iter()
+                .filter(|branch| match &branch.keyload {
+                    None => true,
+                    Some(members) => members.contains(&spec.name),
+                })
+                .map(|branch| branch.name.clone())
+                .collect();
+            expect.insert(spec.name.clone(), readable);
+        }
+
+        Scenario {
+            name: "Random keyload access case".to_string(),
+            channel_type: ScenarioChannelType::MultiBranch,
+            subscribers,
+            branches,
+            expect,
+        }
+    }
+
+    proptest! {
+        // Every case runs a whole channel, so fewer cases are run than proptest's default
+        #![proptest_config(ProptestConfig::with_cases(20))]
+
+        #[test]
+        fn keyloads_grant_access_to_exactly_their_members(scenario in keyload_scenario()) {
+            let transport = MemoryTransport::new();
+            let result = block_on(scenario.run(transport));
+            prop_assert!(
+                result.is_ok(),
+                "{}\nFailing scenario:\n{}",
+                result.unwrap_err(),
+                serde_yaml::to_string(&scenario).unwrap()
+            );
+        }
+    }
+}
@@ -0,0 +1,515 @@
+use anyhow::{bail, ensure, Result};
+use iota_streams::app_channels::api::tangle::{
+    Address, Author, Bytes, MessageContent, Subscriber, Transport, UnwrappedMessage,
+};
+use core::str::FromStr;
+use serde::{Deserialize, Serialize};
+use std::{
+    fs::{self, OpenOptions},
+    io::Write,
+    path::{Path, PathBuf},
+};
+
+use crate::tools::{
+    chunking::{
+        chunk_payload, hash, send_chunk, send_manifest, send_page, CHUNK_TAG, MANIFEST_PAGE_TAG,
+        MANIFEST_TAG,
+    },
+    write_atomic, ChannelWriter, Manifest,
+};
+
+// Public payload marking the signed packet that opens a file transfer
+pub const OFFER_TAG: &[u8] = b"file-transfer-offer";
+
+// Describes the file being transferred, published ahead of its chunks
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FileOffer {
+    pub name: String,
+    pub size: usize,
+    pub hash: String,
+    pub chunk_size: usize,
+    pub chunk_count: usize,
+}
+
+impl FileOffer {
+    pub fn from_message(msg: &UnwrappedMessage) -> Option<Self> {
+        match &msg.body {
+            MessageContent::SignedPacket { public_payload, masked_payload, .. }
+                if public_payload.0 == OFFER_TAG =>
+            {
+                serde_json::from_slice(&masked_payload.0).ok()
+            }
+            _ => None,
+        }
+    }
+}
+
+// How far along a transfer is, reported after every chunk
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Progress {
+    pub chunks_done: usize,
+    pub chunk_count: usize,
+    pub bytes_done: usize,
+    pub size: usize,
+}
+
+// The persisted state of the sending side of a transfer
+#[derive(Clone, Debug, Serialize, Deserialize)]
+struct SendState {
+    offer: FileOffer,
+    // The link the next message of the transfer is attached to
+    prev_link: String,
+    offer_link: Option<String>,
+    chunk_links: Vec<String>,
+    // Links of the manifest pages sent, when the chunk links don't fit in the manifest itself
+    #[serde(default)]
+    page_links: Vec<String>,
+    manifest_link: Option<String>,
+    // Exported Author state as of the last message sent
+    author_state: String,
+    // The link the next message of the transfer is going to be published at, recorded before it
+    // is sent
+    #[serde(default)]
+    in_flight: Option<String>,
+}
+
+/**
+ * The sending side of a file transfer into a branch. The file is published as an offer describing
+ * it, followed by its chunks and finally a manifest of the chunk links, paged as with
+ * `publish_chunked` when they don't fit in a single message.
+ *
+ * After every message sent, the transfer state is persisted together with the exported Author
+ * state in a single write, so that the two never disagree. The link of each message is recorded
+ * before it is sent, so a sender reopened after a crash, with the Author restored from the
+ * transfer state, first checks whether the message it was sending made it to the transport, and
+ * then carries on from the first chunk that wasn't sent.
+ */
+pub struct FileSender {
+    path: PathBuf,
+    state: SendState,
+}
+
+impl FileSender {
+    // Start a new transfer of `payload`, attached to `link_to`
+    pub fn new<P: AsRef<Path>>(
+        state_path: P,
+        name: &str,
+        payload: &[u8],
+        link_to: &Address,
+        chunk_size: usize,
+    ) -> Result<Self> {
+        let chunk_size = chunk_size.max(1);
+        let sender = FileSender {
+            path: state_path.as_ref().to_path_buf(),
+            state: SendState {
+                offer: FileOffer {
+                    name: name.to_string(),
+                    size: payload.len(),
+                    hash: hash(payload),
+                    chunk_size,
+                    chunk_count: (payload.len() + chunk_size - 1) / chunk_size,
+                },
+                prev_link: link_to.to_string(),
+                offer_link: None,
+                chunk_links: Vec::new(),
+                page_links: Vec::new(),
+                manifest_link: None,
+                author_state: String::new(),
+                in_flight: None,
+            },
+        };
+        sender.save()?;
+        Ok(sender)
+    }
+
+    // Reopen a transfer from its persisted state
+    pub fn open<P: AsRef<Path>>(state_path: P) -> Result<Self> {
+        let path = state_path.as_ref().to_path_buf();
+        let state = serde_json::from_str(&fs::read_to_string(&path)?)?;
+        Ok(FileSender { path, state })
+    }
+
+    // The Author as of the last message sent, if the transfer has sent anything yet
+    pub async fn author<T: Transport>(
+        &self,
+        password: &str,
+        transport: T,
+    ) -> Result<Option<Author<T>>> {
+        if self.state.author_state.is_empty() {
+            return Ok(None);
+        }
+        let state = base64::decode(&self.state.author_state)?;
+        Ok(Some(Author::import(&state, password, transport).await?))
+    }
+
+    pub fn offer(&self) -> &FileOffer {
+        &self.state.offer
+    }
+
+    pub fn progress(&self) -> Progress {
+        let chunks_done = self.state.chunk_links.len();
+        Progress {
+            chunks_done,
+            chunk_count: self.state.offer.chunk_count,
+            bytes_done: (chunks_done * self.state.offer.chunk_size).min(self.state.offer.size),
+            size: self.state.offer.size,
+        }
+    }
+
+    pub fn manifest_link(&self) -> Result<Option<Address>> {
+        match &self.state.manifest_link {
+            Some(link) => Ok(Some(Address::from_str(link)?)),
+            None => Ok(None),
+        }
+    }
+
+    /**
+     * Send whatever is left of the transfer, calling `on_progress` after every chunk, and return
+     * the link of the manifest. The payload must be the same file the transfer was started with.
+     */
+    pub async fn send<T: Transport, F: FnMut(&Progress)>(
+        &mut self,
+        author: &mut Author<T>,
+        payload: &[u8],
+        password: &str,
+        mut on_progress: F,
+    ) -> Result<Address> {
+        ensure!(
+            hash(payload) == self.state.offer.hash,
+            "The file has changed since the transfer of {} started", self.state.offer.name
+        );
+
+        self.settle_in_flight(author, password).await?;
+        if self.state.offer_link.is_none() {
+            self.sending(author)?;
+            let (msg_link, _seq) = author.send_signed_packet(
+                &Address::from_str(&self.state.prev_link)?,
+                &Bytes(OFFER_TAG.to_vec()),
+                &Bytes(serde_json::to_vec(&self.state.offer)?),
+            ).await?;
+            self.state.offer_link = Some(msg_link.to_string());
+            self.sent(author, &msg_link, password).await?;
+        }
+
+        let chunk_size = self.state.offer.chunk_size;
+        for chunk in payload.chunks(chunk_size).skip(self.state.chunk_links.len()) {
+            let prev_link = Address::from_str(&self.state.prev_link)?;
+            self.sending(author)?;
+            let (msg_link, _seq) = send_chunk(author, &prev_link, chunk).await?;
+            self.state.chunk_links.push(msg_link.to_string());
+            self.sent(author, &msg_link, password).await?;
+            on_progress(&self.progress());
+        }
+
+        if let Some(manifest_link) = self.manifest_link()? {
+            return Ok(manifest_link);
+        }
+        let mut manifest = Manifest::new(
+            self.state.offer.hash.clone(),
+            self.state.offer.size,
+            self.state.chunk_links.clone(),
+        );
+        let pages = manifest.pages()?;
+        for page_links in pages.iter().skip(self.state.page_links.len()) {
+            let prev_link = Address::from_str(&self.state.prev_link)?;
+            let prev_page = self.state.page_links.last().cloned();
+            self.sending(author)?;
+            let (msg_link, _seq) =
+                send_page(author, &prev_link, page_links, prev_page.as_deref()).await?;
+            self.state.page_links.push(msg_link.to_string());
+            self.sent(author, &msg_link, password).await?;
+        }
+        if !pages.is_empty() {
+            manifest.chunk_links.clear();
+            manifest.last_page = self.state.page_links.last().cloned();
+        }
+        let prev_link = Address::from_str(&self.state.prev_link)?;
+        self.sending(author)?;
+        let (manifest_link, _seq) = send_manifest(author, &prev_link, &manifest).await?;
+        self.state.manifest_link = Some(manifest_link.to_string());
+        self.sent(author, &manifest_link, password).await?;
+        Ok(manifest_link)
+    }
+
+    // Check whether the message being sent when the state was last saved made it to its link. If
+    // it did, the Author catches up with it and it is recorded as sent, going by its tag, otherwise
+    // it is sent again, to the same link, by the rest of the transfer.
+    async fn settle_in_flight<T: Transport>(
+        &mut self,
+        author: &mut Author<T>,
+        password: &str,
+    ) -> Result<()> {
+        let msg_link = match &self.state.in_flight {
+            Some(link) => Address::from_str(link)?,
+            None => return Ok(()),
+        };
+        let msg = match author.receive_msg(&msg_link).await {
+            Ok(msg) => msg,
+            Err(_) => return Ok(()),
+        };
+        let link = Some(msg_link.to_string());
+        match &msg.body {
+            MessageContent::SignedPacket { public_payload, .. } => match &public_payload.0[..] {
+                tag if tag == OFFER_TAG => self.state.offer_link = link,
+                tag if tag == CHUNK_TAG => self.state.chunk_links.extend(link),
+                tag if tag == MANIFEST_PAGE_TAG => self.state.page_links.extend(link),
+                tag if tag == MANIFEST_TAG => self.state.manifest_link = link,
+                _ => bail!("{} is not a message of the transfer", msg_link),
+            },
+            _ => bail!("{} is not a message of the transfer", msg_link),
+        }
+        self.sent(author, &msg_link, password).await
+    }
+
+    // Record the link the next message is going to be published at, before sending it
+    fn sending<T: Transport>(&mut self, author: &mut Author<T>) -> Result<()> {
+        self.state.in_flight = Some(author.next_msg_link()?.to_string());
+        self.save()
+    }
+
+    async fn sent<T: Transport>(
+        &mut self,
+        author: &Author<T>,
+        msg_link: &Address,
+        password: &str,
+    ) -> Result<()> {
+        self.state.in_flight = None;
+        self.state.prev_link = msg_link.to_string();
+        self.state.author_state = base64::encode(author.export(password).await?);
+        self.save()
+    }
+
+    fn save(&self) -> Result<()> {
+        write_atomic(&self.path, serde_json::to_string_pretty(&self.state)?)
+    }
+}
+
+// The persisted state of the receiving side of a transfer
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+struct ReceiveState {
+    offer: Option<FileOffer>,
+    // Publisher of the offer as a hex key, the only publisher whose chunks are taken
+    #[serde(default)]
+    publisher: Option<String>,
+    // The last message of the transfer read, which the next chunk must be linked to
+    #[serde(default)]
+    last_link: Option<String>,
+    chunks_done: usize,
+    // Bytes of the output file known to be complete
+    bytes_done: usize,
+    manifest_link: Option<String>,
+    // Exported Subscriber state as of the last fetch round handled
+    subscriber_state: String,
+}
+
+/**
+ * The receiving side of a file transfer. Chunks are appended to the output file as the Subscriber
+ * reads them, and once the manifest arrives the file is checked against it. Only the chunks of the
+ * publisher of the first offer read are taken, and only those chained on from the offer, so that
+ * other messages in the branch tagged as chunks are left out of the file. As when receiving a
+ * chunked payload, chunks published before chunks were tagged are read all the same.
+ *
+ * After every fetch round, the number of bytes written is persisted together with the exported
+ * Subscriber state. A receiver reopened after a crash, with the Subscriber restored from the
+ * transfer state, drops anything written past that point and carries on reading from the first
+ * chunk it hadn't finished with.
+ */
+pub struct FileReceiver {
+    path: PathBuf,
+    output: PathBuf,
+    state: ReceiveState,
+}
+
+impl FileReceiver {
+    // Open the transfer stored at `state_path`, or start a new one writing to `output`
+    pub fn open<P: AsRef<Path>, O: AsRef<Path>>(state_path: P, output: O) -> Result<Self> {
+        let path = state_path.as_ref().to_path_buf();
+        let state = if path.exists() {
+            serde_json::from_str(&fs::read_to_string(&path)?)?
+        } else {
+            ReceiveState::default()
+        };
+        Ok(FileReceiver {
+            path,
+            output: output.as_ref().to_path_buf(),
+            state,
+        })
+    }
+
+    // The Subscriber as of the last fetch round handled, if the transfer has read anything yet
+    pub async fn subscriber<T: Transport>(
+        &self,
+        password: &str,
+        transport: T,
+    ) -> Result<Option<Subscriber<T>>> {
+        if self.state.subscriber_state.is_empty() {
+            return Ok(None);
+        }
+        let state = base64::decode(&self.state.subscriber_state)?;
+        Ok(Some(Subscriber::import(&state, password, transport).await?))
+    }
+
+    pub fn offer(&self) -> Option<&FileOffer> {
+        self.state.offer.as_ref()
+    }
+
+    pub fn progress(&self) -> Option<Progress> {
+        self.state.offer.as_ref().map(|offer| Progress {
+            chunks_done: self.state.chunks_done,
+            chunk_count: offer.chunk_count,
+            bytes_done: self.state.bytes_done,
+            size: offer.size,
+        })
+    }
+
+    pub fn is_complete(&self) -> bool {
+        self.state.manifest_link.is_some()
+    }
+
+    /**
+     * Read everything the Subscriber can reach, writing chunks of the transfer to the output file
+     * and calling `on_progress` after each. Returns whether the whole file has been received and
+     * verified against the manifest.
+     */
+    pub async fn receive<T: Transport, F: FnMut(&Progress)>(
+        &mut self,
+        subscriber: &mut Subscriber<T>,
+        password: &str,
+        mut on_progress: F,
+    ) -> Result<bool> {
+        if self.is_complete() {
+            return Ok(true);
+        }
+        // Anything written after the last persisted round is read again
+        let mut output = OpenOptions::new().create(true).write(true).open(&self.output)?;
+        output.set_len(self.state.bytes_done as u64)?;
+        drop(output);
+
+        loop {
+            let msgs = subscriber.fetch_next_msgs().await;
+            if msgs.is_empty() {
+                break;
+            }
+            for msg in &msgs {
+                self.handle(msg, &mut on_progress)?;
+            }
+            self.state.subscriber_state = base64::encode(subscriber.export(password).await?);
+            write_atomic(&self.path, serde_json::to_string_pretty(&self.state)?)?;
+            if self.is_complete() {
+                break;
+            }
+        }
+        Ok(self.is_complete())
+    }
+
+    fn handle<F: FnMut(&Progress)>(
+        &mut self,
+        msg: &UnwrappedMessage,
+        on_progress: &mut F,
+    ) -> Result<()> {
+        let (publisher, public_payload) = match &msg.body {
+            MessageContent::SignedPacket { pk, public_payload, .. } => {
+                (hex::encode(pk.as_bytes()), &public_payload.0)
+            }
+            _ => return Ok(()),
+        };
+
+        let offer = match &self.state.offer {
+            Some(offer) => offer.clone(),
+            None => {
+                if let Some(offer) = FileOffer::from_message(msg) {
+                    self.state.offer = Some(offer);
+                    self.state.publisher = Some(publisher);
+                    self.state.last_link = Some(msg.link.to_string());
+                }
+                return Ok(());
+            }
+        };
+        // Only the publisher of the offer takes part in the transfer
+        if self.state.publisher.as_ref() != Some(&publisher) {
+            return Ok(());
+        }
+
+        if public_payload == MANIFEST_TAG {
+            let manifest = match Manifest::from_message(msg) {
+                Some(manifest) => manifest,
+                None => bail!("Malformed manifest at {}", msg.link),
+            };
+            ensure!(
+                self.state.chunks_done == manifest.chunk_count,
+                "Received {} chunks, the manifest lists {}",
+                self.state.chunks_done, manifest.chunk_count
+            );
+            let contents = fs::read(&self.output)?;
+            ensure!(contents.len() == manifest.size, "Received file has the wrong size");
+            ensure!(hash(&contents) == manifest.hash, "Received file does not match the manifest");
+            self.state.manifest_link = Some(msg.link.to_string());
+        } else if let Ok(chunk) = chunk_payload(msg) {
+            // Chunks are chained one after the other from the offer
+            if self.state.last_link != Some(msg.prev_link.to_string()) {
+                return Ok(());
+            }
+            ensure!(
+                self.state.chunks_done < offer.chunk_count,
+                "Received more chunks than the {} the offer of {} lists",
+                offer.chunk_count, offer.name
+            );
+            let mut output = OpenOptions::new().append(true).open(&self.output)?;
+            output.write_all(&chunk)?;
+            self.state.chunks_done += 1;
+            self.state.bytes_done += chunk.len();
+            self.state.last_link = Some(msg.link.to_string());
+            if let Some(progress) = self.progress() {
+                on_progress(&progress);
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::transport::MemoryTransport;
+    use futures::executor::block_on;
+    use iota_streams::app_channels::api::tangle::ChannelType;
+
+    #[test]
+    fn offer_sent_before_a_crash_is_not_sent_again() {
+        block_on(async {
+            let transport = MemoryTransport::new();
+            let mut author =
+                Author::new("TransferAuthor", ChannelType::SingleBranch, transport.clone());
+            let announcement_link = author.send_announce().await?;
+            let name = format!("transfer-{}.json", hex::encode(announcement_link.to_msg_index()));
+            let path = std::env::temp_dir().join(name);
+            let payload = b"0123456789";
+            let mut sender = FileSender::new(&path, "digits", payload, &announcement_link, 4)?;
+            let state = author.export("password").await?;
+
+            // The offer is recorded as in flight and sent, but the device stops before the
+            // transfer state is saved again
+            sender.sending(&mut author)?;
+            author.send_signed_packet(
+                &announcement_link,
+                &Bytes(OFFER_TAG.to_vec()),
+                &Bytes(serde_json::to_vec(sender.offer())?),
+            ).await?;
+
+            let mut sender = FileSender::open(&path)?;
+            let mut author = Author::import(&state, "password", transport.clone()).await?;
+            sender.send(&mut author, payload, "password", |_| ()).await?;
+            fs::remove_file(&path)?;
+
+            let mut subscriber = Subscriber::new("TransferReader", transport);
+            subscriber.receive_announcement(&announcement_link).await?;
+            let msgs = subscriber.fetch_all_next_msgs().await;
+            let offers = msgs.iter().filter(|msg| FileOffer::from_message(msg).is_some()).count();
+            assert_eq!(offers, 1);
+            // The offer, three chunks and the manifest
+            assert_eq!(msgs.len(), 5);
+            Ok::<(), anyhow::Error>(())
+        })
+        .unwrap();
+    }
+}
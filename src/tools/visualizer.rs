@@ -0,0 +1,407 @@
+use anyhow::{bail, Result};
+use iota_streams::app_channels::api::tangle::{Address, MessageContent, UnwrappedMessage};
+use std::{
+    collections::{BTreeSet, HashMap},
+    fmt::Write,
+    fs,
+    path::Path,
+};
+
+use crate::tools::ChannelReader;
+
+// Fill colours cycled through for each branch of the channel
+const BRANCH_COLOURS: [&str; 6] = ["#a6cee3", "#b2df8a", "#fb9a99", "#fdbf6f", "#cab2d6", "#ffff99"];
+const UNKNOWN_COLOUR: &str = "#dddddd";
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum NodeKind {
+    Announce,
+    Keyload,
+    Sequence,
+    SignedPacket,
+    TaggedPacket,
+    Subscribe,
+    // A message linked to by another that none of the walking identities could read
+    Unreadable,
+    Other,
+}
+
+impl NodeKind {
+    pub fn from_content(content: &MessageContent) -> Self {
+        match content {
+            MessageContent::Announce => NodeKind::Announce,
+            MessageContent::Keyload => NodeKind::Keyload,
+            MessageContent::Sequence => NodeKind::Sequence,
+            MessageContent::SignedPacket { .. } => NodeKind::SignedPacket,
+            MessageContent::TaggedPacket { .. } => NodeKind::TaggedPacket,
+            MessageContent::Subscribe => NodeKind::Subscribe,
+            _ => NodeKind::Other,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            NodeKind::Announce => "Announce",
+            NodeKind::Keyload => "Keyload",
+            NodeKind::Sequence => "Sequence",
+            NodeKind::SignedPacket => "SignedPacket",
+            NodeKind::TaggedPacket => "TaggedPacket",
+            NodeKind::Subscribe => "Subscribe",
+            NodeKind::Unreadable => "Unreadable",
+            NodeKind::Other => "Message",
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct ChannelNode {
+    pub link: Address,
+    pub prev_link: Option<Address>,
+    pub kind: NodeKind,
+    // Short form of the signing public key for signed packets
+    pub publisher: Option<String>,
+    // Identities that were able to read this message while walking the channel
+    pub readers: BTreeSet<String>,
+    // For sequence messages, the message they pointed the reader to
+    pub sequenced: Option<Address>,
+}
+
+/**
+ * A graph of the messages in a channel built up by walking it as one or more identities. Each
+ * identity only sees the messages it has access to, so walking as several identities annotates
+ * every node with who is able to read it. The graph can be exported as Graphviz DOT or Mermaid,
+ * with each branch drawn in its own colour.
+ */
+pub struct ChannelGraph {
+    nodes: Vec<ChannelNode>,
+    positions: HashMap<String, usize>,
+}
+
+impl ChannelGraph {
+    pub fn new(announcement_link: &Address) -> Self {
+        let mut graph = ChannelGraph {
+            nodes: Vec::new(),
+            positions: HashMap::new(),
+        };
+        graph.insert(announcement_link.clone(), None, NodeKind::Announce, None);
+        graph
+    }
+
+    pub fn nodes(&self) -> &[ChannelNode] {
+        &self.nodes
+    }
+
+    /**
+     * Fetch every message readable by the reader and add it to the graph under the given identity,
+     * along with the sequence messages read on the way, and return how many messages were fetched.
+     * Only messages the reader hasn't processed yet are returned, which never includes a user's
+     * own messages, so the Author's view of its channel is walked with `walk_back` instead.
+     */
+    pub async fn walk<R: ChannelReader>(&mut self, identity: &str, reader: &mut R) -> usize {
+        let mut walked = 0;
+        loop {
+            // Sequence messages are consumed while fetching and never returned, so they are found
+            // the same way as when archiving: the links polled in a round that have moved on by
+            // the end of it were read
+            let polled = reader.next_publisher_links();
+            let msgs = reader.fetch_next_msgs().await;
+            if msgs.is_empty() {
+                break;
+            }
+            let unread = reader.next_msg_links();
+            self.add_messages(identity, &msgs);
+            self.add_sequences(identity, &polled, &unread, &msgs);
+            walked += msgs.len();
+        }
+        walked
+    }
+
+    /**
+     * Receive each of the `tips` and follow their chains back towards the announcement, adding
+     * every message on the way under the given identity, and return how many were added. A chain
+     * stops at a message this identity has already walked, or at one it can't read, which is left
+     * in the graph as unreadable. Receiving messages updates the reader's state, so the Author
+     * should be walked through an imported copy of itself.
+     */
+    pub async fn walk_back<R: ChannelReader>(
+        &mut self,
+        identity: &str,
+        reader: &mut R,
+        tips: &[Address],
+    ) -> Result<usize> {
+        self.nodes[0].readers.insert(identity.to_string());
+        let mut added = 0;
+        for tip in tips {
+            let mut msg = reader.receive_msg(tip).await?;
+            loop {
+                let prev_walked = self.is_read_by(&msg.prev_link, identity);
+                self.add_messages(identity, std::slice::from_ref(&msg));
+                added += 1;
+                if prev_walked || matches!(msg.body, MessageContent::Announce) {
+                    break;
+                }
+                msg = match reader.fetch_prev_msg(&msg.link).await {
+                    Ok(prev) => prev,
+                    Err(_) => break,
+                };
+            }
+        }
+        Ok(added)
+    }
+
+    fn is_read_by(&self, link: &Address, identity: &str) -> bool {
+        self.positions
+            .get(&link.to_string())
+            .map_or(false, |pos| self.nodes[*pos].readers.contains(identity))
+    }
+
+    pub fn add_messages(&mut self, identity: &str, msgs: &[UnwrappedMessage]) {
+        // Anyone who can walk the channel has read the announcement
+        self.nodes[0].readers.insert(identity.to_string());
+
+        for msg in msgs {
+            let publisher = publisher_key(msg).map(|pk| short_id(&pk));
+            let kind = NodeKind::from_content(&msg.body);
+            let pos = self.insert(msg.link.clone(), Some(msg.prev_link.clone()), kind, publisher);
+            self.nodes[pos].readers.insert(identity.to_string());
+
+            // The message linked to hasn't been returned to any identity so far, and is filled in
+            // if a later walk can read it
+            if !self.positions.contains_key(&msg.prev_link.to_string()) {
+                self.insert(msg.prev_link.clone(), None, NodeKind::Unreadable, None);
+            }
+        }
+    }
+
+    // Add the sequence messages read in a round of fetching. Single branch channels poll the
+    // messages themselves, so only polled links that weren't returned are sequence messages. Each
+    // is linked to the message its publisher sent in the round, or, for messages that don't carry
+    // a public key, to the one message left over.
+    fn add_sequences(
+        &mut self,
+        identity: &str,
+        polled: &[(String, Address)],
+        unread: &[Address],
+        msgs: &[UnwrappedMessage],
+    ) {
+        let read: Vec<&(String, Address)> = polled
+            .iter()
+            .filter(|(_, link)| !unread.contains(link) && !msgs.iter().any(|msg| msg.link == *link))
+            .collect();
+        let sent_by = |publisher: &String| {
+            msgs.iter().find(|msg| publisher_key(msg).as_ref() == Some(publisher))
+        };
+        let mut unmatched: Vec<&UnwrappedMessage> = msgs
+            .iter()
+            .filter(|msg| {
+                !read
+                    .iter()
+                    .any(|(publisher, _)| sent_by(publisher).map(|sent| &sent.link) == Some(&msg.link))
+            })
+            .collect();
+        let unmatched_seqs = read.iter().filter(|(publisher, _)| sent_by(publisher).is_none()).count();
+
+        for (publisher, link) in &read {
+            let sequenced = match sent_by(publisher) {
+                Some(msg) => Some(msg.link.clone()),
+                None if unmatched_seqs == 1 && unmatched.len() == 1 => {
+                    unmatched.pop().map(|msg| msg.link.clone())
+                }
+                None => None,
+            };
+            let pos = self.insert(link.clone(), None, NodeKind::Sequence, Some(short_id(publisher)));
+            self.nodes[pos].readers.insert(identity.to_string());
+            if self.nodes[pos].sequenced.is_none() {
+                self.nodes[pos].sequenced = sequenced;
+            }
+        }
+    }
+
+    fn insert(
+        &mut self,
+        link: Address,
+        prev_link: Option<Address>,
+        kind: NodeKind,
+        publisher: Option<String>,
+    ) -> usize {
+        let id = link.to_string();
+        if let Some(pos) = self.positions.get(&id) {
+            let node = &mut self.nodes[*pos];
+            // A placeholder may be filled in once another identity can read the message
+            if node.prev_link.is_none() && prev_link.is_some() {
+                node.prev_link = prev_link;
+                node.kind = kind;
+                node.publisher = publisher;
+            }
+            return *pos;
+        }
+
+        self.nodes.push(ChannelNode {
+            link,
+            prev_link,
+            kind,
+            publisher,
+            readers: BTreeSet::new(),
+            sequenced: None,
+        });
+        self.positions.insert(id, self.nodes.len() - 1);
+        self.nodes.len() - 1
+    }
+
+    // Assign each node to a branch. Branches start at a keyload (or the announcement for public
+    // branches) and are inherited by every message linked to them.
+    pub fn branches(&self) -> Vec<Option<usize>> {
+        let mut branches: Vec<Option<usize>> = vec![None; self.nodes.len()];
+        let mut roots = 0;
+        for pos in 0..self.nodes.len() {
+            self.branch_of(pos, &mut branches, &mut roots);
+        }
+        branches
+    }
+
+    fn branch_of(&self, pos: usize, branches: &mut [Option<usize>], roots: &mut usize) -> Option<usize> {
+        if let Some(branch) = branches[pos] {
+            return Some(branch);
+        }
+
+        let node = &self.nodes[pos];
+        let branch = match node.kind {
+            NodeKind::Announce | NodeKind::Keyload => {
+                *roots += 1;
+                Some(*roots - 1)
+            }
+            // Sequence messages are drawn in the branch of the message they point to
+            NodeKind::Sequence => node
+                .sequenced
+                .as_ref()
+                .and_then(|sequenced| self.positions.get(&sequenced.to_string()).copied())
+                .and_then(|sequenced| self.branch_of(sequenced, branches, roots)),
+            _ => node
+                .prev_link
+                .as_ref()
+                .and_then(|prev| self.positions.get(&prev.to_string()).copied())
+                .and_then(|prev| self.branch_of(prev, branches, roots)),
+        };
+        branches[pos] = branch;
+        branch
+    }
+
+    // The keyload or announcement link that a message's branch starts from
+    pub fn branch_root(&self, link: &Address) -> Option<Address> {
+        let mut pos = *self.positions.get(&link.to_string())?;
+        // Bounded by the node count in case of a malformed chain
+        for _ in 0..self.nodes.len() {
+            let node = &self.nodes[pos];
+            match node.kind {
+                NodeKind::Announce | NodeKind::Keyload => return Some(node.link.clone()),
+                _ => pos = *self.positions.get(&node.prev_link.as_ref()?.to_string())?,
+            }
+        }
+        None
+    }
+
+    pub fn to_dot(&self) -> String {
+        let branches = self.branches();
+        let mut dot = String::from("digraph channel {\n    rankdir=TB;\n    node [shape=box, style=filled];\n");
+        for (pos, node) in self.nodes.iter().enumerate() {
+            let _ = writeln!(
+                dot,
+                "    n{} [label=\"{}\", fillcolor=\"{}\"];",
+                pos,
+                self.label(node, "\\n"),
+                colour(branches[pos])
+            );
+        }
+        for (pos, prev) in self.edges() {
+            let _ = writeln!(dot, "    n{} -> n{};", prev, pos);
+        }
+        for (pos, sequenced) in self.sequence_edges() {
+            let _ = writeln!(dot, "    n{} -> n{} [style=dashed];", pos, sequenced);
+        }
+        dot.push_str("}\n");
+        dot
+    }
+
+    pub fn to_mermaid(&self) -> String {
+        let branches = self.branches();
+        let mut mermaid = String::from("graph TD\n");
+        for (pos, node) in self.nodes.iter().enumerate() {
+            let _ = writeln!(mermaid, "    n{}[\"{}\"]", pos, self.label(node, "<br/>"));
+        }
+        for (pos, prev) in self.edges() {
+            let _ = writeln!(mermaid, "    n{} --> n{}", prev, pos);
+        }
+        for (pos, sequenced) in self.sequence_edges() {
+            let _ = writeln!(mermaid, "    n{} -.-> n{}", pos, sequenced);
+        }
+        for (pos, branch) in branches.into_iter().enumerate() {
+            let _ = writeln!(mermaid, "    style n{} fill:{}", pos, colour(branch));
+        }
+        mermaid
+    }
+
+    fn edges(&self) -> Vec<(usize, usize)> {
+        self.nodes
+            .iter()
+            .enumerate()
+            .filter_map(|(pos, node)| {
+                node.prev_link
+                    .as_ref()
+                    .and_then(|prev| self.positions.get(&prev.to_string()))
+                    .map(|prev| (pos, *prev))
+            })
+            .collect()
+    }
+
+    // Links from each sequence message to the message it points to
+    fn sequence_edges(&self) -> Vec<(usize, usize)> {
+        self.nodes
+            .iter()
+            .enumerate()
+            .filter_map(|(pos, node)| {
+                node.sequenced
+                    .as_ref()
+                    .and_then(|sequenced| self.positions.get(&sequenced.to_string()))
+                    .map(|sequenced| (pos, *sequenced))
+            })
+            .collect()
+    }
+
+    // Write the graph to `path`, as Mermaid for a `.mmd` extension and Graphviz DOT for `.dot`
+    pub fn write(&self, path: &Path) -> Result<()> {
+        let contents = match path.extension().and_then(|ext| ext.to_str()) {
+            Some("dot") | Some("gv") => self.to_dot(),
+            Some("mmd") => self.to_mermaid(),
+            _ => bail!("Unknown graph format for {}, expected a .dot or .mmd file", path.display()),
+        };
+        fs::write(path, contents)?;
+        Ok(())
+    }
+
+    fn label(&self, node: &ChannelNode, line_break: &str) -> String {
+        let mut label = format!("{}{}{}", node.kind.name(), line_break, short_id(&node.link.to_string()));
+        if let Some(publisher) = &node.publisher {
+            label.push_str(&format!("{}by {}", line_break, publisher));
+        }
+        if !node.readers.is_empty() {
+            let readers = node.readers.iter().cloned().collect::<Vec<String>>().join(", ");
+            label.push_str(&format!("{}readers: {}", line_break, readers));
+        }
+        label
+    }
+}
+
+fn publisher_key(msg: &UnwrappedMessage) -> Option<String> {
+    match &msg.body {
+        MessageContent::SignedPacket { pk, .. } => Some(hex::encode(pk.as_bytes())),
+        _ => None,
+    }
+}
+
+fn colour(branch: Option<usize>) -> &'static str {
+    branch.map_or(UNKNOWN_COLOUR, |branch| BRANCH_COLOURS[branch % BRANCH_COLOURS.len()])
+}
+
+fn short_id(id: &str) -> String {
+    id.chars().rev().take(8).collect::<Vec<char>>().into_iter().rev().collect()
+}
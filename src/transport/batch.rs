@@ -0,0 +1,167 @@
+use anyhow::{anyhow, Result};
+use async_trait::async_trait;
+use futures::stream::{self, StreamExt};
+use iota_streams::{
+    app::transport::{Transport, TransportDetails, TransportOptions},
+    app_channels::api::tangle::{Address, Message, Transport as ChannelTransport},
+};
+use std::{cell::RefCell, fmt, rc::Rc};
+
+#[derive(Default)]
+struct BatchState {
+    deferring: bool,
+    queued: Vec<Message>,
+}
+
+// The messages of a submission that failed to send, which are queued on the transport again
+#[derive(Debug)]
+pub struct SubmitError {
+    pub total: usize,
+    pub unsent: Vec<Address>,
+    pub cause: anyhow::Error,
+}
+
+impl fmt::Display for SubmitError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} of {} messages failed to send: {}", self.unsent.len(), self.total, self.cause)
+    }
+}
+
+impl std::error::Error for SubmitError {}
+
+/**
+ * A transport that can hold messages back instead of sending them straight away. Streams computes
+ * the link of every message locally, so a whole chain of messages can be prepared by a user while
+ * sending is deferred, without waiting on the transport in between. The queued messages are then
+ * submitted together with bounded concurrency. Queued messages can already be received through the
+ * transport before they are submitted.
+ */
+#[derive(Clone)]
+pub struct BatchTransport<T> {
+    inner: T,
+    state: Rc<RefCell<BatchState>>,
+}
+
+impl<T: ChannelTransport> BatchTransport<T> {
+    pub fn new(inner: T) -> Self {
+        BatchTransport {
+            inner,
+            state: Rc::new(RefCell::new(BatchState::default())),
+        }
+    }
+
+    pub fn inner(&self) -> &T {
+        &self.inner
+    }
+
+    // Hold back messages sent from now on until they are submitted
+    pub fn defer(&self) {
+        self.state.borrow_mut().deferring = true;
+    }
+
+    pub fn queued(&self) -> usize {
+        self.state.borrow().queued.len()
+    }
+
+    /**
+     * Stop deferring and send every queued message to the inner transport, with at most
+     * `concurrency` sends in flight at once. Messages that fail to send are queued again, and
+     * their links are reported in a `SubmitError`.
+     */
+    pub async fn submit(&self, concurrency: usize) -> Result<usize> {
+        let queued = {
+            let mut state = self.state.borrow_mut();
+            state.deferring = false;
+            std::mem::take(&mut state.queued)
+        };
+        let total = queued.len();
+
+        let failed = stream::iter(queued)
+            .map(|msg| {
+                let mut inner = self.inner.clone();
+                async move {
+                    match inner.send_message(&msg).await {
+                        Ok(()) => None,
+                        Err(e) => Some((msg, e)),
+                    }
+                }
+            })
+            .buffer_unordered(concurrency.max(1))
+            .filter_map(|failure| async move { failure })
+            .collect::<Vec<(Message, anyhow::Error)>>()
+            .await;
+
+        if failed.is_empty() {
+            return Ok(total);
+        }
+        let mut unsent = Vec::new();
+        let mut cause = None;
+        let mut state = self.state.borrow_mut();
+        for (msg, e) in failed {
+            unsent.push(msg.binary.link.clone());
+            state.queued.push(msg);
+            cause.get_or_insert(e);
+        }
+        Err(SubmitError {
+            total,
+            unsent,
+            cause: cause.unwrap_or_else(|| anyhow!("Unknown send failure")),
+        }
+        .into())
+    }
+}
+
+#[async_trait(?Send)]
+impl<T: ChannelTransport> Transport<Address, Message> for BatchTransport<T> {
+    async fn send_message(&mut self, msg: &Message) -> Result<()> {
+        {
+            let mut state = self.state.borrow_mut();
+            if state.deferring {
+                state.queued.push(msg.clone());
+                return Ok(());
+            }
+        }
+        self.inner.send_message(msg).await
+    }
+
+    async fn recv_messages(&mut self, link: &Address) -> Result<Vec<Message>> {
+        let queued = self
+            .state
+            .borrow()
+            .queued
+            .iter()
+            .filter(|msg| &msg.binary.link == link)
+            .cloned()
+            .collect::<Vec<Message>>();
+        if !queued.is_empty() {
+            return Ok(queued);
+        }
+        self.inner.recv_messages(link).await
+    }
+}
+
+#[async_trait(?Send)]
+impl<T: ChannelTransport> TransportDetails<Address> for BatchTransport<T> {
+    type Details = <T as TransportDetails<Address>>::Details;
+    async fn get_link_details(&mut self, link: &Address) -> Result<Self::Details> {
+        self.inner.get_link_details(link).await
+    }
+}
+
+impl<T: TransportOptions> TransportOptions for BatchTransport<T> {
+    type SendOptions = T::SendOptions;
+    fn get_send_options(&self) -> Self::SendOptions {
+        self.inner.get_send_options()
+    }
+    fn set_send_options(&mut self, opt: Self::SendOptions) {
+        self.inner.set_send_options(opt)
+    }
+
+    type RecvOptions = T::RecvOptions;
+    fn get_recv_options(&self) -> Self::RecvOptions {
+        self.inner.get_recv_options()
+    }
+    fn set_recv_options(&mut self, opt: Self::RecvOptions) {
+        self.inner.set_recv_options(opt)
+    }
+}
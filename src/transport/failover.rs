@@ -0,0 +1,443 @@
+use anyhow::{anyhow, ensure, Result};
+use async_trait::async_trait;
+use iota_streams::{
+    app::transport::{tangle::client::Client, Transport, TransportDetails, TransportOptions},
+    app_channels::api::tangle::{Address, Message, Transport as ChannelTransport},
+};
+use std::{cell::RefCell, fmt, rc::Rc, time::Duration};
+
+use crate::transport::is_not_found;
+
+// How long a node has to accept a connection before it is considered unhealthy
+const HEALTH_TIMEOUT: Duration = Duration::from_secs(5);
+
+// A way of telling whether the node behind a transport is reachable
+#[async_trait(?Send)]
+pub trait HealthCheck {
+    async fn check_health(&mut self, url: &str) -> Result<()>;
+}
+
+// A node is healthy when it accepts connections on the host and port of its url
+#[async_trait(?Send)]
+impl HealthCheck for Client {
+    async fn check_health(&mut self, url: &str) -> Result<()> {
+        let addr = socket_addr(url)?;
+        tokio::time::timeout(HEALTH_TIMEOUT, tokio::net::TcpStream::connect(&addr))
+            .await
+            .map_err(|_| anyhow!("Node {} did not respond within {:?}", url, HEALTH_TIMEOUT))??;
+        Ok(())
+    }
+}
+
+// The host and port of a node url, defaulting the port from the scheme
+fn socket_addr(url: &str) -> Result<String> {
+    let (default_port, rest) = match url.split_once("://") {
+        Some(("https", rest)) => (443, rest),
+        Some(("http", rest)) => (80, rest),
+        Some((scheme, _)) => return Err(anyhow!("Unsupported scheme '{}' in node url {}", scheme, url)),
+        None => (443, url),
+    };
+    let host = rest.split('/').next().unwrap_or(rest);
+    ensure!(!host.is_empty(), "Node url {} has no host", url);
+    if host.contains(':') {
+        Ok(host.to_string())
+    } else {
+        Ok(format!("{}:{}", host, default_port))
+    }
+}
+
+// How often, and how patiently, operations are retried when no node can serve them
+#[derive(Clone, Copy, Debug)]
+pub struct RetryPolicy {
+    pub max_attempts: usize,
+    pub initial_backoff: Duration,
+    pub max_backoff: Duration,
+    pub multiplier: u32,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        RetryPolicy {
+            max_attempts: 5,
+            initial_backoff: Duration::from_millis(500),
+            max_backoff: Duration::from_secs(30),
+            multiplier: 2,
+        }
+    }
+}
+
+impl RetryPolicy {
+    // The wait before the given attempt, growing exponentially up to the maximum
+    pub fn backoff(&self, attempt: usize) -> Duration {
+        let factor = self.multiplier.saturating_pow(attempt.saturating_sub(1) as u32);
+        self.initial_backoff
+            .checked_mul(factor)
+            .map_or(self.max_backoff, |backoff| backoff.min(self.max_backoff))
+    }
+}
+
+// Changes in which nodes serve requests, reported to the listener of the transport
+#[derive(Clone, Debug)]
+pub enum FailoverEvent {
+    // A node that was set aside passed its health check again
+    Recovered(String),
+    // Requests are now sent to this node first
+    FailedOver(String),
+    Unhealthy { url: String, error: String },
+    Retrying { backoff: Duration, attempt: usize, max_attempts: usize },
+}
+
+impl fmt::Display for FailoverEvent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FailoverEvent::Recovered(url) => write!(f, "Node {} is healthy again", url),
+            FailoverEvent::FailedOver(url) => write!(f, "Failed over to node {}", url),
+            FailoverEvent::Unhealthy { url, error } => write!(f, "Node {} is unhealthy: {}", url, error),
+            FailoverEvent::Retrying { backoff, attempt, max_attempts } => {
+                write!(f, "Retrying in {:?} (attempt {} of {})", backoff, attempt, max_attempts)
+            }
+        }
+    }
+}
+
+// Which nodes are currently considered healthy, shared between clones of the transport
+#[derive(Debug)]
+struct FailoverState {
+    healthy: Vec<bool>,
+    current: usize,
+}
+
+/**
+ * A transport spread over several nodes. Requests go to the node that last served one
+ * successfully, and when a request fails the node is health checked: unhealthy nodes are set aside
+ * and the request fails over to the next node. A node reporting a message as not found is trusted
+ * without a health check (the message simply doesn't exist yet), while any other error from a
+ * healthy node is treated as transient. Nodes that were set aside are checked again before each attempt so they
+ * are picked back up once they recover.
+ *
+ * When no node can serve a request, it is retried with exponential backoff according to the
+ * retry policy. Sends are also retried when nodes are reachable but reject the message. Failovers,
+ * health changes and retries are reported to the listener given with `with_listener`.
+ */
+#[derive(Clone)]
+pub struct FailoverTransport<T> {
+    nodes: Vec<(String, T)>,
+    policy: RetryPolicy,
+    state: Rc<RefCell<FailoverState>>,
+    listener: Option<Rc<dyn Fn(&FailoverEvent)>>,
+}
+
+impl FailoverTransport<Client> {
+    pub fn from_urls(urls: &[&str], policy: RetryPolicy) -> Result<Self> {
+        Self::new(
+            urls.iter()
+                .map(|url| (url.to_string(), Client::new_from_url(url)))
+                .collect(),
+            policy,
+        )
+    }
+}
+
+impl<T: ChannelTransport + HealthCheck> FailoverTransport<T> {
+    // Nodes are given as (url, transport) pairs, in order of preference
+    pub fn new(nodes: Vec<(String, T)>, policy: RetryPolicy) -> Result<Self> {
+        ensure!(!nodes.is_empty(), "A failover transport needs at least one node");
+        let state = FailoverState {
+            healthy: vec![true; nodes.len()],
+            current: 0,
+        };
+        Ok(FailoverTransport {
+            nodes,
+            policy,
+            state: Rc::new(RefCell::new(state)),
+            listener: None,
+        })
+    }
+
+    // Report failover events to `listener`, which is shared by clones made afterwards
+    pub fn with_listener<F: Fn(&FailoverEvent) + 'static>(mut self, listener: F) -> Self {
+        self.listener = Some(Rc::new(listener));
+        self
+    }
+
+    fn report(&self, event: FailoverEvent) {
+        if let Some(listener) = &self.listener {
+            listener(&event);
+        }
+    }
+
+    pub fn policy(&self) -> &RetryPolicy {
+        &self.policy
+    }
+
+    // The url of the node requests are currently sent to first
+    pub fn current_node(&self) -> &str {
+        &self.nodes[self.state.borrow().current].0
+    }
+
+    pub fn healthy_nodes(&self) -> Vec<&str> {
+        let state = self.state.borrow();
+        self.nodes
+            .iter()
+            .zip(&state.healthy)
+            .filter(|(_, healthy)| **healthy)
+            .map(|((url, _), _)| url.as_str())
+            .collect()
+    }
+
+    // Health check every node, returning the urls of the healthy ones
+    pub async fn check_nodes(&mut self) -> Vec<&str> {
+        for i in 0..self.nodes.len() {
+            let (url, mut node) = self.nodes[i].clone();
+            let healthy = node.check_health(&url).await.is_ok();
+            self.state.borrow_mut().healthy[i] = healthy;
+        }
+        self.healthy_nodes()
+    }
+
+    // The healthy nodes in the order they should be tried, starting from the current node. Nodes
+    // that were set aside are checked again first.
+    async fn candidates(&self) -> Vec<usize> {
+        let (current, healthy) = {
+            let state = self.state.borrow();
+            (state.current, state.healthy.clone())
+        };
+        let mut candidates = Vec::new();
+        for i in (0..self.nodes.len()).map(|offset| (current + offset) % self.nodes.len()) {
+            if !healthy[i] {
+                let (url, mut node) = self.nodes[i].clone();
+                if node.check_health(&url).await.is_err() {
+                    continue;
+                }
+                self.state.borrow_mut().healthy[i] = true;
+                self.report(FailoverEvent::Recovered(url));
+            }
+            candidates.push(i);
+        }
+        candidates
+    }
+
+    fn succeeded(&self, i: usize) {
+        let failed_over = {
+            let mut state = self.state.borrow_mut();
+            let failed_over = state.current != i;
+            state.current = i;
+            failed_over
+        };
+        if failed_over {
+            self.report(FailoverEvent::FailedOver(self.nodes[i].0.clone()));
+        }
+    }
+
+    // Health check a node after a failed request, setting it aside if it is unhealthy
+    async fn failed(&self, i: usize) {
+        let (url, mut node) = self.nodes[i].clone();
+        if let Err(e) = node.check_health(&url).await {
+            self.state.borrow_mut().healthy[i] = false;
+            self.report(FailoverEvent::Unhealthy { url, error: e.to_string() });
+        }
+    }
+
+    async fn wait(&self, attempt: usize) {
+        if attempt > 0 {
+            let backoff = self.policy.backoff(attempt);
+            self.report(FailoverEvent::Retrying {
+                backoff,
+                attempt: attempt + 1,
+                max_attempts: self.policy.max_attempts,
+            });
+            tokio::time::sleep(backoff).await;
+        }
+    }
+
+    fn exhausted(&self, last_err: Option<anyhow::Error>) -> anyhow::Error {
+        match last_err {
+            Some(e) => anyhow!("Request failed on every node after {} attempts: {}", self.policy.max_attempts, e),
+            None => anyhow!("No healthy node available after {} attempts", self.policy.max_attempts),
+        }
+    }
+}
+
+#[async_trait(?Send)]
+impl<T: ChannelTransport + HealthCheck> Transport<Address, Message> for FailoverTransport<T> {
+    async fn send_message(&mut self, msg: &Message) -> Result<()> {
+        let mut last_err = None;
+        for attempt in 0..self.policy.max_attempts {
+            self.wait(attempt).await;
+            for i in self.candidates().await {
+                let mut node = self.nodes[i].1.clone();
+                match node.send_message(msg).await {
+                    Ok(()) => {
+                        self.succeeded(i);
+                        return Ok(());
+                    }
+                    Err(e) => {
+                        self.failed(i).await;
+                        last_err = Some(e);
+                    }
+                }
+            }
+        }
+        Err(self.exhausted(last_err))
+    }
+
+    async fn recv_messages(&mut self, link: &Address) -> Result<Vec<Message>> {
+        let mut last_err = None;
+        for attempt in 0..self.policy.max_attempts {
+            self.wait(attempt).await;
+            for i in self.candidates().await {
+                let mut node = self.nodes[i].1.clone();
+                match node.recv_messages(link).await {
+                    Ok(msgs) => {
+                        self.succeeded(i);
+                        return Ok(msgs);
+                    }
+                    Err(e) => {
+                        // The node answered that the message isn't there, so it is reachable and
+                        // the message missing rather than the request failed
+                        if is_not_found(&e) {
+                            return Err(e);
+                        }
+                        self.failed(i).await;
+                        last_err = Some(e);
+                    }
+                }
+            }
+        }
+        Err(self.exhausted(last_err))
+    }
+}
+
+#[async_trait(?Send)]
+impl<T: ChannelTransport + HealthCheck> TransportDetails<Address> for FailoverTransport<T> {
+    type Details = <T as TransportDetails<Address>>::Details;
+    async fn get_link_details(&mut self, link: &Address) -> Result<Self::Details> {
+        let mut last_err = None;
+        for attempt in 0..self.policy.max_attempts {
+            self.wait(attempt).await;
+            for i in self.candidates().await {
+                let mut node = self.nodes[i].1.clone();
+                match node.get_link_details(link).await {
+                    Ok(details) => {
+                        self.succeeded(i);
+                        return Ok(details);
+                    }
+                    Err(e) => {
+                        if is_not_found(&e) {
+                            return Err(e);
+                        }
+                        self.failed(i).await;
+                        last_err = Some(e);
+                    }
+                }
+            }
+        }
+        Err(self.exhausted(last_err))
+    }
+}
+
+impl<T> TransportOptions for FailoverTransport<T>
+where
+    T: TransportOptions,
+    T::SendOptions: Clone,
+    T::RecvOptions: Clone,
+{
+    type SendOptions = T::SendOptions;
+    fn get_send_options(&self) -> Self::SendOptions {
+        self.nodes[0].1.get_send_options()
+    }
+    fn set_send_options(&mut self, opt: Self::SendOptions) {
+        for (_, node) in self.nodes.iter_mut() {
+            node.set_send_options(opt.clone());
+        }
+    }
+
+    type RecvOptions = T::RecvOptions;
+    fn get_recv_options(&self) -> Self::RecvOptions {
+        self.nodes[0].1.get_recv_options()
+    }
+    fn set_recv_options(&mut self, opt: Self::RecvOptions) {
+        for (_, node) in self.nodes.iter_mut() {
+            node.set_recv_options(opt.clone());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::transport::{MemoryTransport, NotFound, StandInNode};
+    use core::str::FromStr;
+    use iota_streams::app_channels::api::tangle::{Author, ChannelType};
+
+    fn single_node() -> (StandInNode, FailoverTransport<StandInNode>) {
+        let node = StandInNode::new(MemoryTransport::new());
+        let policy = RetryPolicy {
+            max_attempts: 3,
+            initial_backoff: Duration::from_millis(1),
+            max_backoff: Duration::from_millis(10),
+            multiplier: 2,
+        };
+        let nodes = vec![("http://node-0.local".to_string(), node.clone())];
+        (node, FailoverTransport::new(nodes, policy).unwrap())
+    }
+
+    // The backoff between attempts sleeps on the tokio timer
+    fn run<F: std::future::Future<Output = Result<()>>>(future: F) -> Result<()> {
+        tokio::runtime::Builder::new_current_thread()
+            .enable_time()
+            .build()?
+            .block_on(future)
+    }
+
+    #[test]
+    fn fetch_failing_once_is_retried() {
+        run(async {
+            let (node, mut transport) = single_node();
+            let mut author =
+                Author::new("FailoverAuthor", ChannelType::SingleBranch, transport.clone());
+            let announcement_link = author.send_announce().await?;
+
+            node.fail_next(1);
+            let requests = node.requests();
+            let msgs = transport.recv_messages(&announcement_link).await?;
+            assert_eq!(msgs.len(), 1);
+            assert_eq!(node.requests() - requests, 2, "Expected one failed fetch and one retry");
+            Ok(())
+        })
+        .unwrap();
+    }
+
+    #[test]
+    fn missing_message_is_not_retried() {
+        run(async {
+            let (node, mut transport) = single_node();
+            let mut author =
+                Author::new("FailoverAuthor", ChannelType::SingleBranch, transport.clone());
+            let announcement_link = author.send_announce().await?;
+            let ann_link_string = announcement_link.to_string();
+            let (appinst, msgid) = ann_link_string.split_once(':').unwrap();
+            let missing_msgid = "0".repeat(msgid.len());
+            let missing_link = Address::from_str(&format!("{}:{}", appinst, missing_msgid))?;
+
+            let requests = node.requests();
+            let error = transport.recv_messages(&missing_link).await.unwrap_err();
+            assert!(is_not_found(&error), "Expected a not found error, got: {}", error);
+            assert_eq!(node.requests() - requests, 1, "A missing message should not be retried");
+            Ok(())
+        })
+        .unwrap();
+    }
+
+    #[test]
+    fn only_not_found_errors_end_a_fetch() {
+        assert!(is_not_found(&missing_in("memory").into()));
+        assert!(is_not_found(&anyhow::Error::from(missing_in("archive")).context("Fetching")));
+        assert!(!is_not_found(&anyhow!("Stand-in node failed the request: not found")));
+    }
+
+    fn missing_in(store: &str) -> NotFound {
+        let link = format!("{}:{}", "0".repeat(80), "0".repeat(24));
+        NotFound { link: Address::from_str(&link).unwrap(), store: store.to_string() }
+    }
+}
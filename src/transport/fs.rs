@@ -0,0 +1,123 @@
+use anyhow::Result;
+use async_trait::async_trait;
+use fs2::FileExt;
+use iota_streams::{
+    app::transport::{Transport, TransportDetails, TransportOptions},
+    app_channels::api::tangle::{Address, Message},
+};
+use std::{
+    fs::{self, File, OpenOptions},
+    io::{ErrorKind, Read, Write},
+    path::{Path, PathBuf},
+};
+
+use crate::transport::{decode_message, encode_message, NotFound};
+
+/**
+ * A transport that stores messages as files in a shared directory instead of attaching them to
+ * the tangle. Each file is named after the tangle index of the messages it holds, so several
+ * processes pointed at the same directory behave as if they were talking to the same node. Files
+ * are locked while being read or written, allowing concurrent publishers.
+ */
+#[derive(Clone, Debug)]
+pub struct FileTransport {
+    root: PathBuf,
+}
+
+impl FileTransport {
+    pub fn new<P: AsRef<Path>>(root: P) -> Result<Self> {
+        fs::create_dir_all(root.as_ref())?;
+        Ok(FileTransport {
+            root: root.as_ref().to_path_buf(),
+        })
+    }
+
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    fn index_path(&self, link: &Address) -> PathBuf {
+        self.root.join(hex::encode(link.to_msg_index()))
+    }
+
+    fn read_index(&self, link: &Address) -> Result<Vec<Message>> {
+        let mut file = match File::open(self.index_path(link)) {
+            Ok(file) => file,
+            Err(e) if e.kind() == ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(e.into()),
+        };
+
+        let mut bytes = Vec::new();
+        file.lock_shared()?;
+        let read = file.read_to_end(&mut bytes);
+        file.unlock()?;
+        read?;
+
+        let mut msgs = Vec::new();
+        let mut pos = 0;
+        while pos + 4 <= bytes.len() {
+            let mut len = [0u8; 4];
+            len.copy_from_slice(&bytes[pos..pos + 4]);
+            let start = pos + 4;
+            let end = start + u32::from_le_bytes(len) as usize;
+            if end > bytes.len() {
+                // A writer may still be appending this record
+                break;
+            }
+            msgs.push(decode_message(&bytes[start..end])?);
+            pos = end;
+        }
+        Ok(msgs)
+    }
+}
+
+#[async_trait(?Send)]
+impl Transport<Address, Message> for FileTransport {
+    async fn send_message(&mut self, msg: &Message) -> Result<()> {
+        let record = encode_message(msg);
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(self.index_path(&msg.binary.link))?;
+
+        file.lock_exclusive()?;
+        let written = file
+            .write_all(&(record.len() as u32).to_le_bytes())
+            .and_then(|_| file.write_all(&record))
+            .and_then(|_| file.sync_data());
+        file.unlock()?;
+        written?;
+        Ok(())
+    }
+
+    async fn recv_messages(&mut self, link: &Address) -> Result<Vec<Message>> {
+        let msgs: Vec<Message> = self
+            .read_index(link)?
+            .into_iter()
+            .filter(|msg| &msg.binary.link == link)
+            .collect();
+
+        if msgs.is_empty() {
+            return Err(NotFound { link: link.clone(), store: self.root.display().to_string() }.into());
+        }
+        Ok(msgs)
+    }
+}
+
+#[async_trait(?Send)]
+impl TransportDetails<Address> for FileTransport {
+    type Details = ();
+    async fn get_link_details(&mut self, _link: &Address) -> Result<Self::Details> {
+        Ok(())
+    }
+}
+
+impl TransportOptions for FileTransport {
+    type SendOptions = ();
+    fn get_send_options(&self) {}
+    fn set_send_options(&mut self, _opt: ()) {}
+
+    type RecvOptions = ();
+    fn get_recv_options(&self) {}
+    fn set_recv_options(&mut self, _opt: ()) {}
+}
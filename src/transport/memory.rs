@@ -0,0 +1,91 @@
+use anyhow::Result;
+use async_trait::async_trait;
+use iota_streams::{
+    app::transport::{Transport, TransportDetails, TransportOptions},
+    app_channels::api::tangle::{Address, Message},
+};
+use std::{cell::RefCell, collections::HashMap, rc::Rc};
+
+use crate::transport::NotFound;
+
+/**
+ * An offline transport keeping messages in memory. Clones share the same messages, and the stored
+ * messages can be inspected and modified directly, which makes it possible to simulate tampered or
+ * replayed messages.
+ */
+#[derive(Clone, Default)]
+pub struct MemoryTransport {
+    messages: Rc<RefCell<HashMap<Address, Vec<Message>>>>,
+}
+
+impl MemoryTransport {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn messages_at(&self, link: &Address) -> Vec<Message> {
+        self.messages.borrow().get(link).cloned().unwrap_or_default()
+    }
+
+    // Store a message at whatever link it carries, without any checks
+    pub fn insert(&self, msg: Message) {
+        self.messages
+            .borrow_mut()
+            .entry(msg.binary.link.clone())
+            .or_default()
+            .push(msg);
+    }
+
+    pub fn replace(&self, link: &Address, msgs: Vec<Message>) {
+        self.messages.borrow_mut().insert(link.clone(), msgs);
+    }
+
+    pub fn remove(&self, link: &Address) -> Vec<Message> {
+        self.messages.borrow_mut().remove(link).unwrap_or_default()
+    }
+
+    // Modify the messages stored at a link in place, returning false if there are none
+    pub fn tamper<F: FnMut(&mut Message)>(&self, link: &Address, mut f: F) -> bool {
+        match self.messages.borrow_mut().get_mut(link) {
+            Some(msgs) => {
+                msgs.iter_mut().for_each(&mut f);
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+#[async_trait(?Send)]
+impl Transport<Address, Message> for MemoryTransport {
+    async fn send_message(&mut self, msg: &Message) -> Result<()> {
+        self.insert(msg.clone());
+        Ok(())
+    }
+
+    async fn recv_messages(&mut self, link: &Address) -> Result<Vec<Message>> {
+        let msgs = self.messages_at(link);
+        if msgs.is_empty() {
+            return Err(NotFound { link: link.clone(), store: "memory".to_string() }.into());
+        }
+        Ok(msgs)
+    }
+}
+
+#[async_trait(?Send)]
+impl TransportDetails<Address> for MemoryTransport {
+    type Details = ();
+    async fn get_link_details(&mut self, _link: &Address) -> Result<Self::Details> {
+        Ok(())
+    }
+}
+
+impl TransportOptions for MemoryTransport {
+    type SendOptions = ();
+    fn get_send_options(&self) {}
+    fn set_send_options(&mut self, _opt: ()) {}
+
+    type RecvOptions = ();
+    fn get_recv_options(&self) {}
+    fn set_recv_options(&mut self, _opt: ()) {}
+}
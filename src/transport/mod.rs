@@ -0,0 +1,93 @@
+pub mod batch;
+pub mod failover;
+pub mod fs;
+pub mod memory;
+pub mod prefetch;
+pub mod sqlite;
+pub mod stand_in;
+
+pub use batch::{BatchTransport, SubmitError};
+pub use failover::{FailoverEvent, FailoverTransport, HealthCheck, RetryPolicy};
+pub use fs::FileTransport;
+pub use memory::MemoryTransport;
+pub use prefetch::{Prefetched, PrefetchTransport};
+pub use sqlite::{ArchivedMessage, SqliteTransport};
+pub use stand_in::StandInNode;
+
+use anyhow::{ensure, Result};
+use iota_streams::{
+    app::{message::BinaryMessage, transport::tangle::TangleMessage},
+    app_channels::api::tangle::{Address, Message},
+    core::{Errors, WrappedError},
+};
+use std::fmt;
+
+// The error the local transports return for a link with no message behind it
+#[derive(Debug)]
+pub struct NotFound {
+    pub link: Address,
+    // Where the message was looked for
+    pub store: String,
+}
+
+impl fmt::Display for NotFound {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Message at link {} not found in {}", self.link, self.store)
+    }
+}
+
+impl std::error::Error for NotFound {}
+
+// Whether an error reports the link as missing rather than the request as failed, either from one
+// of the local transports or from a node
+pub(crate) fn is_not_found(e: &anyhow::Error) -> bool {
+    let node_not_found = matches!(
+        e.downcast_ref::<WrappedError<Errors>>(),
+        Some(WrappedError(Errors::MessageLinkNotFoundInTangle(_)))
+    );
+    node_not_found || e.chain().any(|cause| cause.is::<NotFound>())
+}
+
+// Messages are stored as a length prefixed link, previous link, timestamp and body so that they
+// can be rebuilt without needing to consult the tangle
+pub fn encode_message(msg: &Message) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    write_field(&mut bytes, &msg.binary.link.to_bytes());
+    write_field(&mut bytes, &msg.binary.prev_link.to_bytes());
+    bytes.extend_from_slice(&msg.timestamp.to_le_bytes());
+    write_field(&mut bytes, &msg.binary.body.bytes);
+    bytes
+}
+
+// Rebuild a message previously serialised with `encode_message`
+pub fn decode_message(bytes: &[u8]) -> Result<Message> {
+    let mut pos = 0;
+    let link = Address::from_bytes(read_field(bytes, &mut pos)?);
+    let prev_link = Address::from_bytes(read_field(bytes, &mut pos)?);
+    ensure!(bytes.len() >= pos + 8, "Truncated message record");
+    let mut timestamp = [0u8; 8];
+    timestamp.copy_from_slice(&bytes[pos..pos + 8]);
+    pos += 8;
+    let body = read_field(bytes, &mut pos)?.to_vec();
+
+    Ok(TangleMessage::with_timestamp(
+        BinaryMessage::new(link, prev_link, body.into()),
+        u64::from_le_bytes(timestamp),
+    ))
+}
+
+fn write_field(bytes: &mut Vec<u8>, field: &[u8]) {
+    bytes.extend_from_slice(&(field.len() as u32).to_le_bytes());
+    bytes.extend_from_slice(field);
+}
+
+fn read_field<'a>(bytes: &'a [u8], pos: &mut usize) -> Result<&'a [u8]> {
+    ensure!(bytes.len() >= *pos + 4, "Truncated message record");
+    let mut len = [0u8; 4];
+    len.copy_from_slice(&bytes[*pos..*pos + 4]);
+    let start = *pos + 4;
+    let end = start + u32::from_le_bytes(len) as usize;
+    ensure!(bytes.len() >= end, "Truncated message record");
+    *pos = end;
+    Ok(&bytes[start..end])
+}
@@ -0,0 +1,189 @@
+use anyhow::{bail, Result};
+use async_trait::async_trait;
+use futures::{
+    future::poll_fn,
+    stream::{self, StreamExt},
+};
+use iota_streams::{
+    app::transport::{Transport, TransportDetails, TransportOptions},
+    app_channels::api::tangle::{Address, Message, Transport as ChannelTransport},
+};
+use std::{cell::RefCell, collections::HashMap, future::Future, rc::Rc};
+
+use crate::transport::is_not_found;
+
+// The outcome of a prefetch: how many links were found, and the links whose requests failed for any
+// reason other than there being no message at them
+#[derive(Debug, Default)]
+pub struct Prefetched {
+    pub found: usize,
+    pub failed: Vec<(Address, anyhow::Error)>,
+}
+
+/**
+ * A transport that can fetch a set of links ahead of time, with several requests in flight at
+ * once. Prefetched messages are kept until they are received through the transport, so a user
+ * reading over it gets them without another round trip. Anything that wasn't prefetched is
+ * received from the inner transport as usual. Prefetched messages that end up not being read are
+ * kept until they are evicted.
+ *
+ * When the links a user is going to request can't be worked out up front, the transport can
+ * record them instead: every link requested by the recorded operation is noted and failed straight
+ * away, without a round trip, so that the recorded links can be prefetched and the requests
+ * repeated. Recording only applies while that operation is running, so other users sharing the
+ * transport carry on as usual.
+ */
+#[derive(Clone)]
+pub struct PrefetchTransport<T> {
+    inner: T,
+    state: Rc<RefCell<PrefetchState>>,
+}
+
+#[derive(Default)]
+struct PrefetchState {
+    cache: HashMap<Address, Vec<Message>>,
+    recording: Option<Vec<Address>>,
+}
+
+impl<T: ChannelTransport> PrefetchTransport<T> {
+    pub fn new(inner: T) -> Self {
+        PrefetchTransport {
+            inner,
+            state: Rc::new(RefCell::new(PrefetchState::default())),
+        }
+    }
+
+    pub fn inner(&self) -> &T {
+        &self.inner
+    }
+
+    /**
+     * Receive the given links from the inner transport with at most `concurrency` requests in
+     * flight. Links that don't exist yet are skipped, while links whose request failed in any
+     * other way are reported along with their errors, and are requested again from the inner
+     * transport when received.
+     */
+    pub async fn prefetch(&self, links: Vec<Address>, concurrency: usize) -> Prefetched {
+        let mut prefetched = Prefetched::default();
+        let found = self.fetch_uncached(links, concurrency, &mut prefetched).await;
+        self.state.borrow_mut().cache.extend(found);
+        prefetched
+    }
+
+    async fn fetch_uncached(
+        &self,
+        links: Vec<Address>,
+        concurrency: usize,
+        prefetched: &mut Prefetched,
+    ) -> Vec<(Address, Vec<Message>)> {
+        let results = stream::iter(links)
+            .filter(|link| {
+                let cached = self.contains(link);
+                async move { !cached }
+            })
+            .map(|link| {
+                let mut inner = self.inner.clone();
+                async move {
+                    let result = inner.recv_messages(&link).await;
+                    (link, result)
+                }
+            })
+            .buffer_unordered(concurrency.max(1))
+            .collect::<Vec<(Address, Result<Vec<Message>>)>>()
+            .await;
+
+        let mut found = Vec::new();
+        for (link, result) in results {
+            match result {
+                Ok(msgs) => found.push((link, msgs)),
+                Err(e) if is_not_found(&e) => (),
+                Err(e) => prefetched.failed.push((link, e)),
+            }
+        }
+        prefetched.found += found.len();
+        found
+    }
+
+    // Drop every prefetched message that hasn't been received, returning how many were dropped
+    pub fn evict(&self) -> usize {
+        let mut state = self.state.borrow_mut();
+        let evicted = state.cache.len();
+        state.cache.clear();
+        evicted
+    }
+
+    pub fn prefetched(&self) -> usize {
+        self.state.borrow().cache.len()
+    }
+
+    pub fn contains(&self, link: &Address) -> bool {
+        self.state.borrow().cache.contains_key(link)
+    }
+
+    /**
+     * Run `operation`, recording the links it requests instead of receiving them, and return its
+     * output along with the links in the order they were requested. Recording is only switched on
+     * while the operation itself is being polled, so requests made in the meantime by anything
+     * else reading over the transport are received as usual.
+     */
+    pub async fn recording<F: Future>(&self, operation: F) -> (F::Output, Vec<Address>) {
+        let mut operation = Box::pin(operation);
+        let mut recorded = Some(Vec::new());
+        let output = poll_fn(|cx| {
+            self.state.borrow_mut().recording = recorded.take();
+            let poll = operation.as_mut().poll(cx);
+            recorded = self.state.borrow_mut().recording.take();
+            poll
+        })
+        .await;
+        (output, recorded.unwrap_or_default())
+    }
+}
+
+#[async_trait(?Send)]
+impl<T: ChannelTransport> Transport<Address, Message> for PrefetchTransport<T> {
+    async fn send_message(&mut self, msg: &Message) -> Result<()> {
+        self.inner.send_message(msg).await
+    }
+
+    async fn recv_messages(&mut self, link: &Address) -> Result<Vec<Message>> {
+        let cached = {
+            let mut state = self.state.borrow_mut();
+            if let Some(recording) = state.recording.as_mut() {
+                recording.push(link.clone());
+                bail!("Link {} was recorded, not received", link);
+            }
+            state.cache.remove(link)
+        };
+        match cached {
+            Some(msgs) => Ok(msgs),
+            None => self.inner.recv_messages(link).await,
+        }
+    }
+}
+
+#[async_trait(?Send)]
+impl<T: ChannelTransport> TransportDetails<Address> for PrefetchTransport<T> {
+    type Details = <T as TransportDetails<Address>>::Details;
+    async fn get_link_details(&mut self, link: &Address) -> Result<Self::Details> {
+        self.inner.get_link_details(link).await
+    }
+}
+
+impl<T: TransportOptions> TransportOptions for PrefetchTransport<T> {
+    type SendOptions = T::SendOptions;
+    fn get_send_options(&self) -> Self::SendOptions {
+        self.inner.get_send_options()
+    }
+    fn set_send_options(&mut self, opt: Self::SendOptions) {
+        self.inner.set_send_options(opt)
+    }
+
+    type RecvOptions = T::RecvOptions;
+    fn get_recv_options(&self) -> Self::RecvOptions {
+        self.inner.get_recv_options()
+    }
+    fn set_recv_options(&mut self, opt: Self::RecvOptions) {
+        self.inner.set_recv_options(opt)
+    }
+}
@@ -0,0 +1,175 @@
+use anyhow::{anyhow, Result};
+use async_trait::async_trait;
+use iota_streams::{
+    app::{
+        message::BinaryMessage,
+        transport::{tangle::TangleMessage, Transport, TransportDetails, TransportOptions},
+    },
+    app_channels::api::tangle::{Address, Message},
+};
+use rusqlite::{params, Connection};
+use core::str::FromStr;
+use std::{
+    path::Path,
+    sync::{Arc, Mutex},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use crate::transport::NotFound;
+
+const SELECT_MESSAGES: &str =
+    "SELECT address, prev_address, msg_index, timestamp, raw, inserted_at FROM messages";
+
+/**
+ * A durable transport that keeps every message in a local SQLite database. Besides being usable
+ * in place of a `Client`, the database doubles as an archive of the channel: each row records the
+ * message address, its tangle index, the raw message bytes and the time it was inserted.
+ */
+#[derive(Clone)]
+pub struct SqliteTransport {
+    conn: Arc<Mutex<Connection>>,
+}
+
+// A row of the message archive
+#[derive(Clone, Debug)]
+pub struct ArchivedMessage {
+    pub address: String,
+    pub prev_address: String,
+    pub msg_index: String,
+    pub timestamp: u64,
+    pub raw: Vec<u8>,
+    pub inserted_at: u64,
+}
+
+impl SqliteTransport {
+    pub fn new<P: AsRef<Path>>(path: P) -> Result<Self> {
+        Self::from_connection(Connection::open(path)?)
+    }
+
+    // Useful for throwaway channels, nothing is written to disk
+    pub fn in_memory() -> Result<Self> {
+        Self::from_connection(Connection::open_in_memory()?)
+    }
+
+    fn from_connection(conn: Connection) -> Result<Self> {
+        conn.execute_batch(
+            "CREATE TABLE IF NOT EXISTS messages (
+                id           INTEGER PRIMARY KEY AUTOINCREMENT,
+                address      TEXT NOT NULL,
+                prev_address TEXT NOT NULL,
+                msg_index    TEXT NOT NULL,
+                timestamp    INTEGER NOT NULL,
+                raw          BLOB NOT NULL,
+                inserted_at  INTEGER NOT NULL
+            );
+            CREATE INDEX IF NOT EXISTS messages_by_index ON messages (msg_index);",
+        )?;
+        Ok(SqliteTransport {
+            conn: Arc::new(Mutex::new(conn)),
+        })
+    }
+
+    // Every stored message in insertion order
+    pub fn archive(&self) -> Result<Vec<ArchivedMessage>> {
+        self.query(&format!("{} ORDER BY id", SELECT_MESSAGES), params![])
+    }
+
+    // Stored messages inserted within `[from, to)`, as seconds since the unix epoch
+    pub fn archive_between(&self, from: u64, to: u64) -> Result<Vec<ArchivedMessage>> {
+        self.query(
+            &format!("{} WHERE inserted_at >= ?1 AND inserted_at < ?2 ORDER BY id", SELECT_MESSAGES),
+            params![from as i64, to as i64],
+        )
+    }
+
+    pub fn message_count(&self) -> Result<usize> {
+        let conn = self.conn.lock().map_err(|_| anyhow!("Sqlite connection poisoned"))?;
+        let count: i64 = conn.query_row("SELECT COUNT(*) FROM messages", params![], |row| row.get(0))?;
+        Ok(count as usize)
+    }
+
+    fn query(&self, sql: &str, args: &[&dyn rusqlite::ToSql]) -> Result<Vec<ArchivedMessage>> {
+        let conn = self.conn.lock().map_err(|_| anyhow!("Sqlite connection poisoned"))?;
+        let mut stmt = conn.prepare(sql)?;
+        let rows = stmt.query_map(args, |row| {
+            Ok(ArchivedMessage {
+                address: row.get(0)?,
+                prev_address: row.get(1)?,
+                msg_index: row.get(2)?,
+                timestamp: row.get::<_, i64>(3)? as u64,
+                raw: row.get(4)?,
+                inserted_at: row.get::<_, i64>(5)? as u64,
+            })
+        })?;
+        Ok(rows.collect::<rusqlite::Result<Vec<ArchivedMessage>>>()?)
+    }
+}
+
+impl ArchivedMessage {
+    // Rebuild the Streams message this row was created from
+    pub fn to_message(&self) -> Result<Message> {
+        Ok(TangleMessage::with_timestamp(
+            BinaryMessage::new(
+                Address::from_str(&self.address)?,
+                Address::from_str(&self.prev_address)?,
+                self.raw.clone().into(),
+            ),
+            self.timestamp,
+        ))
+    }
+}
+
+#[async_trait(?Send)]
+impl Transport<Address, Message> for SqliteTransport {
+    async fn send_message(&mut self, msg: &Message) -> Result<()> {
+        let inserted_at = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs();
+        let conn = self.conn.lock().map_err(|_| anyhow!("Sqlite connection poisoned"))?;
+        conn.execute(
+            "INSERT INTO messages (address, prev_address, msg_index, timestamp, raw, inserted_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            params![
+                msg.binary.link.to_string(),
+                msg.binary.prev_link.to_string(),
+                hex::encode(msg.binary.link.to_msg_index()),
+                msg.timestamp as i64,
+                msg.binary.body.bytes,
+                inserted_at as i64,
+            ],
+        )?;
+        Ok(())
+    }
+
+    async fn recv_messages(&mut self, link: &Address) -> Result<Vec<Message>> {
+        let msgs = self
+            .query(
+                &format!("{} WHERE msg_index = ?1 AND address = ?2 ORDER BY id", SELECT_MESSAGES),
+                params![hex::encode(link.to_msg_index()), link.to_string()],
+            )?
+            .iter()
+            .map(ArchivedMessage::to_message)
+            .collect::<Result<Vec<Message>>>()?;
+
+        if msgs.is_empty() {
+            return Err(NotFound { link: link.clone(), store: "archive".to_string() }.into());
+        }
+        Ok(msgs)
+    }
+}
+
+#[async_trait(?Send)]
+impl TransportDetails<Address> for SqliteTransport {
+    type Details = ();
+    async fn get_link_details(&mut self, _link: &Address) -> Result<Self::Details> {
+        Ok(())
+    }
+}
+
+impl TransportOptions for SqliteTransport {
+    type SendOptions = ();
+    fn get_send_options(&self) {}
+    fn set_send_options(&mut self, _opt: ()) {}
+
+    type RecvOptions = ();
+    fn get_recv_options(&self) {}
+    fn set_recv_options(&mut self, _opt: ()) {}
+}
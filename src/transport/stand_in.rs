@@ -0,0 +1,120 @@
+use anyhow::{anyhow, Result};
+use async_trait::async_trait;
+use iota_streams::{
+    app::transport::{Transport, TransportDetails, TransportOptions},
+    app_channels::api::tangle::{Address, Message},
+};
+use std::{cell::RefCell, rc::Rc, time::Duration};
+
+use crate::transport::{failover::HealthCheck, MemoryTransport};
+
+#[derive(Debug, Default)]
+struct StandInState {
+    down: bool,
+    // Requests that will fail while the node still reports itself as healthy
+    failing_requests: usize,
+    requests: usize,
+    // Delay added to every request, simulating the round trip to a remote node
+    latency: Duration,
+}
+
+/**
+ * A local stand-in for a node that can be made to fail on demand. Stand-ins created over the same
+ * `MemoryTransport` share their messages, like nodes of the same network do. A stand-in can be
+ * taken down entirely, failing both its requests and its health checks, or made to fail a number
+ * of requests while still reporting itself as healthy, simulating transient errors. A latency can
+ * be added to every request to simulate the round trip to a remote node.
+ */
+#[derive(Clone, Default)]
+pub struct StandInNode {
+    storage: MemoryTransport,
+    state: Rc<RefCell<StandInState>>,
+}
+
+impl StandInNode {
+    pub fn new(storage: MemoryTransport) -> Self {
+        StandInNode {
+            storage,
+            state: Rc::new(RefCell::new(StandInState::default())),
+        }
+    }
+
+    pub fn go_down(&self) {
+        self.state.borrow_mut().down = true;
+    }
+
+    pub fn come_up(&self) {
+        self.state.borrow_mut().down = false;
+    }
+
+    pub fn fail_next(&self, requests: usize) {
+        self.state.borrow_mut().failing_requests = requests;
+    }
+
+    pub fn set_latency(&self, latency: Duration) {
+        self.state.borrow_mut().latency = latency;
+    }
+
+    // The number of requests this node has received, whether they succeeded or not
+    pub fn requests(&self) -> usize {
+        self.state.borrow().requests
+    }
+
+    async fn serve(&self) -> Result<()> {
+        let latency = self.state.borrow().latency;
+        if latency > Duration::default() {
+            tokio::time::sleep(latency).await;
+        }
+        let mut state = self.state.borrow_mut();
+        state.requests += 1;
+        if state.down {
+            return Err(anyhow!("Stand-in node is down"));
+        }
+        if state.failing_requests > 0 {
+            state.failing_requests -= 1;
+            return Err(anyhow!("Stand-in node failed the request"));
+        }
+        Ok(())
+    }
+}
+
+#[async_trait(?Send)]
+impl HealthCheck for StandInNode {
+    async fn check_health(&mut self, url: &str) -> Result<()> {
+        if self.state.borrow().down {
+            return Err(anyhow!("Stand-in node {} is down", url));
+        }
+        Ok(())
+    }
+}
+
+#[async_trait(?Send)]
+impl Transport<Address, Message> for StandInNode {
+    async fn send_message(&mut self, msg: &Message) -> Result<()> {
+        self.serve().await?;
+        self.storage.send_message(msg).await
+    }
+
+    async fn recv_messages(&mut self, link: &Address) -> Result<Vec<Message>> {
+        self.serve().await?;
+        self.storage.recv_messages(link).await
+    }
+}
+
+#[async_trait(?Send)]
+impl TransportDetails<Address> for StandInNode {
+    type Details = ();
+    async fn get_link_details(&mut self, _link: &Address) -> Result<Self::Details> {
+        self.serve().await
+    }
+}
+
+impl TransportOptions for StandInNode {
+    type SendOptions = ();
+    fn get_send_options(&self) {}
+    fn set_send_options(&mut self, _opt: ()) {}
+
+    type RecvOptions = ();
+    fn get_recv_options(&self) {}
+    fn set_recv_options(&mut self, _opt: ()) {}
+}